@@ -0,0 +1,180 @@
+use std::{path::PathBuf, str::FromStr};
+
+use thiserror::Error;
+
+use crate::game2::{Difficulty, Dimension, GlyphSupport, Mode};
+
+pub const USAGE: &str = "\
+Usage: my-snake [OPTIONS]
+
+Options:
+  -W, --width <N|N%>     board width in cells, or in percent of the room available [default: 80%]
+  -H, --height <N|N%>    board height in cells, or in percent of the room available [default: 80%]
+  -s, --speed <SPEED>    easy, normal, hard, insane, or the starting tick in milliseconds
+      --seed <N>         seed for candy placement, to replay a game
+  -m, --mode <MODE>      classic (walls kill) or wrap (walls lead to the other side) [default: classic]
+  -t, --theme <THEME>    classic, neon, mono, a theme in the themes directory, or a .toml file [default: classic]
+      --no-color         draw without colors, as does setting NO_COLOR [default: colors guessed from the terminal]
+  -g, --glyphs <SET>     ascii, unicode, or nerd for Nerd Font icons [default: guessed from the terminal]
+  -c, --config <FILE>    read settings from FILE instead of the user's config directory
+      --snapshot <FILE>  write the final board to FILE as text when a round ends
+      --scores           print the high score tables and exit
+  -h, --help             print this help and exit
+";
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("unknown option {0:?}")]
+    UnknownOption(String),
+    #[error("{0} needs a value")]
+    MissingValue(String),
+    #[error("invalid value {value:?} for {option}: {reason}")]
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: &'static str,
+    },
+}
+
+/// How fast to play: a difficulty preset, or just the starting tick.
+#[derive(Debug, Clone, Copy)]
+pub enum SpeedArg {
+    Preset(Difficulty),
+    InitialMs(u64),
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub width: Dimension,
+    pub height: Dimension,
+    pub speed: Option<SpeedArg>,
+    pub seed: Option<u64>,
+    pub mode: Mode,
+    pub theme: Option<String>,
+    pub no_color: bool,
+    pub glyphs: Option<GlyphSupport>,
+    pub config: Option<PathBuf>,
+    pub snapshot: Option<PathBuf>,
+    pub scores: bool,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            width: Dimension::Percent(80),
+            height: Dimension::Percent(80),
+            speed: None,
+            seed: None,
+            mode: Mode::default(),
+            theme: None,
+            no_color: false,
+            glyphs: None,
+            config: None,
+            snapshot: None,
+            scores: false,
+            help: false,
+        }
+    }
+}
+
+fn parse_dimension(value: &str) -> Result<Dimension, &'static str> {
+    match value.strip_suffix('%') {
+        Some(percent) => match percent.parse() {
+            Ok(percent @ 1..=100) => Ok(Dimension::Percent(percent)),
+            _ => Err("expected a percentage between 1% and 100%"),
+        },
+        None => value
+            .parse()
+            .map(Dimension::Cells)
+            .map_err(|_| "expected a number of cells or a percentage"),
+    }
+}
+
+fn parse_speed(value: &str) -> Result<SpeedArg, &'static str> {
+    let preset = match value {
+        "easy" => Difficulty::Easy,
+        "normal" => Difficulty::Normal,
+        "hard" => Difficulty::Hard,
+        "insane" => Difficulty::Insane,
+        ms => {
+            return match ms.parse() {
+                Ok(ms) if ms > 0 => Ok(SpeedArg::InitialMs(ms)),
+                _ => Err("expected easy, normal, hard, insane or milliseconds"),
+            }
+        }
+    };
+    Ok(SpeedArg::Preset(preset))
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => {
+                    (option.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError::MissingValue(option.clone()))
+            };
+            let invalid = |value: String, reason| CliError::InvalidValue {
+                option: option.clone(),
+                value,
+                reason,
+            };
+
+            match option.as_str() {
+                "-W" | "--width" => {
+                    let value = value()?;
+                    parsed.width =
+                        parse_dimension(&value).map_err(|reason| invalid(value, reason))?;
+                }
+                "-H" | "--height" => {
+                    let value = value()?;
+                    parsed.height =
+                        parse_dimension(&value).map_err(|reason| invalid(value, reason))?;
+                }
+                "-s" | "--speed" => {
+                    let value = value()?;
+                    parsed.speed =
+                        Some(parse_speed(&value).map_err(|reason| invalid(value, reason))?);
+                }
+                "--seed" => {
+                    let value = value()?;
+                    parsed.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| invalid(value, "expected a number"))?,
+                    );
+                }
+                "-m" | "--mode" => {
+                    let value = value()?;
+                    parsed.mode = Mode::from_str(&value)
+                        .map_err(|_| invalid(value, "expected classic or wrap"))?;
+                }
+                "-t" | "--theme" => parsed.theme = Some(value()?),
+                "--no-color" => parsed.no_color = true,
+                "-g" | "--glyphs" => {
+                    let value = value()?;
+                    parsed.glyphs = Some(
+                        GlyphSupport::from_str(&value)
+                            .map_err(|_| invalid(value, "expected ascii, unicode or nerd"))?,
+                    );
+                }
+                "-c" | "--config" => parsed.config = Some(PathBuf::from(value()?)),
+                "--snapshot" => parsed.snapshot = Some(PathBuf::from(value()?)),
+                "--scores" => parsed.scores = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(CliError::UnknownOption(option)),
+            }
+        }
+        Ok(parsed)
+    }
+}
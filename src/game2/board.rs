@@ -0,0 +1,212 @@
+use thiserror::Error;
+
+/// The smallest board, in cells, that leaves room to move.
+pub const MIN_WIDTH: u16 = 8;
+pub const MIN_HEIGHT: u16 = 6;
+
+/// Lines under the frame kept free for the status line.
+pub const HUD_ROWS: u16 = 1;
+
+/// A cell of the board, counted from the top left one inside the frame. The
+/// game only ever deals in these; [`Board::project`] is the one place that
+/// knows how they land on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Position {
+    pub fn new(x: u16, y: u16) -> Self {
+        Self { x, y }
+    }
+}
+
+/// One side of the board, as asked for on the command line.
+#[derive(Debug, Clone, Copy)]
+pub enum Dimension {
+    Cells(u16),
+    /// Share of the room the terminal has for the board, from 1 to 100.
+    Percent(u16),
+}
+
+#[derive(Error, Debug)]
+pub enum BoardError {
+    #[error("the board must be at least {MIN_WIDTH}x{MIN_HEIGHT} cells, got {0}x{1}")]
+    TooSmall(u16, u16),
+    #[error("a {0}x{1} board needs a terminal of at least {2}x{3}, this one is {4}x{5}")]
+    DoesNotFit(u16, u16, u16, u16, u16, u16),
+}
+
+/// Where the board sits on the terminal. A board of `width` cells spans
+/// `width * cell_width + 2` columns including the frame, and `height + 2`
+/// rows plus the status line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Board {
+    /// Cells across, inside the frame.
+    pub width: u16,
+    /// Cells down, inside the frame.
+    pub height: u16,
+    /// Terminal columns per cell.
+    pub cell_width: u16,
+    /// Screen position of the top left corner of the frame.
+    pub origin: (u16, u16),
+}
+
+impl Board {
+    /// Sizes a board for a terminal of `term_cols` by `term_rows` and centers
+    /// it there, frame and status line included.
+    pub fn fit(
+        width: Dimension,
+        height: Dimension,
+        cell_width: u16,
+        (term_cols, term_rows): (u16, u16),
+    ) -> Result<Self, BoardError> {
+        let max_width = term_cols.saturating_sub(2) / cell_width;
+        let max_height = term_rows.saturating_sub(2 + HUD_ROWS);
+        let width = match width {
+            Dimension::Cells(cells) => cells,
+            Dimension::Percent(percent) => (max_width as u32 * percent as u32 / 100) as u16,
+        };
+        let height = match height {
+            Dimension::Cells(cells) => cells,
+            Dimension::Percent(percent) => (max_height as u32 * percent as u32 / 100) as u16,
+        };
+
+        if width < MIN_WIDTH || height < MIN_HEIGHT {
+            return Err(BoardError::TooSmall(width, height));
+        }
+        if width > max_width || height > max_height {
+            return Err(BoardError::DoesNotFit(
+                width,
+                height,
+                width.saturating_mul(cell_width).saturating_add(2),
+                height.saturating_add(2 + HUD_ROWS),
+                term_cols,
+                term_rows,
+            ));
+        }
+
+        let mut board = Self {
+            width,
+            height,
+            cell_width,
+            origin: (0, 0),
+        };
+        let (n_cols, n_rows) = board.frame_size();
+        board.origin = (
+            (term_cols - (n_cols + 1)) / 2,
+            (term_rows - (n_rows + 1 + HUD_ROWS)) / 2,
+        );
+        Ok(board)
+    }
+
+    /// The same board, centered on a terminal of `term_size`.
+    pub fn refit(self, term_size: (u16, u16)) -> Result<Self, BoardError> {
+        Self::fit(
+            Dimension::Cells(self.width),
+            Dimension::Cells(self.height),
+            self.cell_width,
+            term_size,
+        )
+    }
+
+    /// Cells across and down.
+    pub fn cells(self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Columns and rows from the top left corner of the frame to the bottom
+    /// right one.
+    pub fn frame_size(self) -> (u16, u16) {
+        (self.width * self.cell_width + 1, self.height + 1)
+    }
+
+    /// The first column and the row of `pos`, relative to the frame.
+    pub fn project(self, pos: Position) -> (u16, u16) {
+        (1 + pos.x * self.cell_width, 1 + pos.y)
+    }
+
+    /// Where a position relative to the frame lands on the terminal.
+    pub fn to_screen(self, (c, r): (u16, u16)) -> (u16, u16) {
+        (self.origin.0 + c, self.origin.1 + r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit(width: Dimension, height: Dimension, term: (u16, u16)) -> Result<Board, BoardError> {
+        Board::fit(width, height, 2, term)
+    }
+
+    #[test]
+    fn width_is_columns_and_height_is_rows() {
+        let board = fit(Dimension::Cells(30), Dimension::Cells(8), (100, 20)).unwrap();
+        assert_eq!(board.cells(), (30, 8));
+        assert_eq!(board.frame_size(), (61, 9));
+        // 62 columns and 9 + 1 rows of frame and a status line, centered.
+        assert_eq!(board.origin, (19, 4));
+        // Too tall for 20 rows the other way round.
+        assert!(matches!(
+            fit(Dimension::Cells(8), Dimension::Cells(30), (100, 20)),
+            Err(BoardError::DoesNotFit(8, 30, 18, 33, 100, 20))
+        ));
+    }
+
+    #[test]
+    fn percent_is_a_share_of_the_room_left() {
+        // 39 cells across and 21 down fit in 80 by 24.
+        let board = fit(Dimension::Percent(50), Dimension::Percent(100), (80, 24)).unwrap();
+        assert_eq!(board.cells(), (19, 21));
+        assert_eq!(board.origin, (20, 0));
+    }
+
+    #[test]
+    fn the_largest_board_fills_the_terminal() {
+        let board = fit(Dimension::Cells(39), Dimension::Cells(21), (80, 24)).unwrap();
+        assert_eq!(board.origin, (0, 0));
+        assert!(matches!(
+            fit(Dimension::Cells(40), Dimension::Cells(21), (80, 24)),
+            Err(BoardError::DoesNotFit(40, 21, 82, 24, 80, 24))
+        ));
+        assert!(matches!(
+            fit(Dimension::Cells(39), Dimension::Cells(22), (80, 24)),
+            Err(BoardError::DoesNotFit(39, 22, 80, 25, 80, 24))
+        ));
+    }
+
+    #[test]
+    fn boards_below_the_minimum_are_too_small() {
+        assert!(matches!(
+            fit(Dimension::Cells(7), Dimension::Cells(6), (80, 24)),
+            Err(BoardError::TooSmall(7, 6))
+        ));
+        assert!(matches!(
+            fit(Dimension::Cells(8), Dimension::Cells(5), (80, 24)),
+            Err(BoardError::TooSmall(8, 5))
+        ));
+        assert!(matches!(
+            fit(Dimension::Percent(10), Dimension::Percent(10), (80, 24)),
+            Err(BoardError::TooSmall(3, 2))
+        ));
+        assert!(fit(Dimension::Cells(8), Dimension::Cells(6), (80, 24)).is_ok());
+    }
+
+    #[test]
+    fn cells_land_inside_the_frame_at_the_origin() {
+        let board = fit(Dimension::Cells(30), Dimension::Cells(8), (100, 20)).unwrap();
+        assert_eq!(board.project(Position::new(0, 0)), (1, 1));
+        assert_eq!(board.project(Position::new(2, 3)), (5, 4));
+        assert_eq!(board.to_screen(board.project(Position::new(2, 3))), (24, 8));
+    }
+
+    #[test]
+    fn refit_keeps_the_size_and_centers_again() {
+        let board = fit(Dimension::Percent(50), Dimension::Percent(50), (80, 24)).unwrap();
+        let refit = board.refit((120, 40)).unwrap();
+        assert_eq!(refit.cells(), board.cells());
+        assert_eq!(refit.origin, (40, 13));
+    }
+}
@@ -1,76 +1,64 @@
 use std::{
-    collections::BTreeSet,
-    io,
-    sync::{mpsc, Arc, Mutex},
-    time,
+    collections::{BTreeSet, HashSet},
+    time::Duration,
 };
 
-use crossterm::{cursor, style, ExecutableCommand};
-use rand::Rng;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
-pub struct CandySpawner {
-    pub locs: Arc<Mutex<BTreeSet<(u16, u16)>>>,
-    stdout: Arc<Mutex<io::Stdout>>,
-    term_size: (u16, u16),
+use super::{board::Position, snake::Snake};
+
+/// Places candy using its own RNG so a board can be replayed from a seed.
+pub struct CandySpawner<R = StdRng> {
+    board: (u16, u16),
+    rng: R,
 }
 
 impl CandySpawner {
-    pub fn new(
-        term_size: (u16, u16),
-        stdout: Arc<Mutex<io::Stdout>>,
-        candy_list: Arc<Mutex<BTreeSet<(u16, u16)>>>,
-    ) -> Self {
-        Self {
-            locs: candy_list,
-            stdout,
-            term_size,
-        }
+    pub fn seeded(board: (u16, u16), seed: u64) -> Self {
+        Self::new(board, StdRng::seed_from_u64(seed))
     }
+}
 
-    fn get_new_candy(&self) -> (u16, u16) {
-        let get_candy = || {
-            (
-                rand::thread_rng().gen_range(2, (self.term_size.0 - 2) / 2) * 2,
-                rand::thread_rng().gen_range(2, self.term_size.1 - 2),
-            )
-        };
-        loop {
-            let candy = get_candy();
-            if self.locs.lock().unwrap().contains(&candy) {
-                continue;
-            }
-            return candy;
-        }
+impl<R: Rng> CandySpawner<R> {
+    pub fn new(board: (u16, u16), rng: R) -> Self {
+        Self { board, rng }
     }
 
-    pub fn add_new_candy(&mut self) {
-        let (c, r) = self.get_new_candy();
-        self.locs.lock().unwrap().insert((c, r));
-        self.stdout
-            .lock()
-            .unwrap()
-            .execute(cursor::MoveTo(c, r))
-            .unwrap()
-            .execute(style::Print(''))
-            .unwrap();
+    /// Picks uniformly among the cells that hold neither snake
+    /// nor candy, or `None` once there are none left.
+    pub fn get_new_candy(&mut self, snake: &Snake, locs: &BTreeSet<Position>) -> Option<Position> {
+        let (width, height) = self.board;
+        let taken: HashSet<&Position> = snake.cells().chain(locs).collect();
+        let free: Vec<Position> = (0..width)
+            .flat_map(|x| (0..height).map(move |y| Position::new(x, y)))
+            .filter(|cell| !taken.contains(cell))
+            .collect();
+        free.choose(&mut self.rng).copied()
     }
+}
+
+/// Counts game time between candy drops. It only moves forward when the game
+/// ticks, so a frozen game never spawns candy.
+pub struct SpawnTimer {
+    interval: Duration,
+    elapsed: Duration,
+}
 
-    fn received_stop_signal(receiver: &mpsc::Receiver<&str>) -> bool {
-        match receiver.try_recv() {
-            Ok(value) => value == "Stop",
-            _ => false,
+impl SpawnTimer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            elapsed: Duration::ZERO,
         }
     }
 
-    pub fn run(&mut self, receiver: mpsc::Receiver<&str>) {
-        self.add_new_candy();
-        let mut tp = time::Instant::now();
-        while false == CandySpawner::received_stop_signal(&receiver) {
-            if (time::Instant::now() - tp) < time::Duration::from_secs(4) {
-                continue;
-            }
-            tp = time::Instant::now();
-            self.add_new_candy();
+    /// Advances by `dt` and tells whether a candy is due.
+    pub fn advance(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+        if self.elapsed < self.interval {
+            return false;
         }
+        self.elapsed -= self.interval;
+        true
     }
 }
@@ -0,0 +1,145 @@
+use crossterm::style::Color;
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    None,
+    /// The 16 named colors.
+    Ansi16,
+    /// The xterm palette of 256.
+    Ansi256,
+    /// Any RGB color.
+    TrueColor,
+}
+
+/// How many shades a color gradient has.
+pub const GRADIENT_STEPS: usize = 16;
+
+/// The named colors with the RGB values xterm gives them.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel values of the 6x6x6 color cube in the 256 color palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The RGB value of an entry in the 256 color palette.
+fn ansi_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE[i as usize / 36],
+                CUBE[i as usize / 6 % 6],
+                CUBE[i as usize % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// The entry of the 256 color palette closest to `rgb`, from the color
+/// cube or the grey ramp.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let channel = |value: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| (CUBE[i] as i32 - value as i32).abs())
+            .unwrap() as u8
+    };
+    let cube = 16 + 36 * channel(rgb.0) + 6 * channel(rgb.1) + channel(rgb.2);
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+    [cube, grey]
+        .into_iter()
+        .min_by_key(|&index| distance(ansi_rgb(index), rgb))
+        .unwrap()
+}
+
+/// The RGB value of `color`, if it has a fixed one.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb { r, g, b } => Some((r, g, b)),
+        Color::AnsiValue(index) => Some(ansi_rgb(index)),
+        named => ANSI16
+            .iter()
+            .find(|(ansi, _)| *ansi == named)
+            .map(|&(_, rgb)| rgb),
+    }
+}
+
+/// Shades going evenly from `from` to `to`, both included. There are none
+/// when either is the terminal's own color.
+pub fn gradient(from: Color, to: Color) -> Option<[Color; GRADIENT_STEPS]> {
+    let (from, to) = (rgb(from)?, rgb(to)?);
+    let last = GRADIENT_STEPS as u32 - 1;
+    let mix = |a: u8, b: u8, step: usize| {
+        let step = step as u32;
+        ((a as u32 * (last - step) + b as u32 * step) / last) as u8
+    };
+    Some(std::array::from_fn(|step| Color::Rgb {
+        r: mix(from.0, to.0, step),
+        g: mix(from.1, to.1, step),
+        b: mix(from.2, to.2, step),
+    }))
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, named)| distance(*named, rgb))
+        .unwrap()
+        .0
+}
+
+impl ColorSupport {
+    /// The closest `color` this terminal can show.
+    pub fn downsample(self, color: Color) -> Color {
+        let rgb = match color {
+            Color::Reset => return color,
+            Color::Rgb { r, g, b } => (r, g, b),
+            Color::AnsiValue(index) => ansi_rgb(index),
+            // Every terminal with colors has the named ones.
+            named => {
+                return if self == ColorSupport::None {
+                    Color::Reset
+                } else {
+                    named
+                }
+            }
+        };
+        match self {
+            ColorSupport::None => Color::Reset,
+            ColorSupport::Ansi16 => nearest_ansi16(rgb),
+            ColorSupport::Ansi256 => match color {
+                Color::AnsiValue(_) => color,
+                _ => Color::AnsiValue(nearest_ansi256(rgb)),
+            },
+            ColorSupport::TrueColor => color,
+        }
+    }
+}
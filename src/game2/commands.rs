@@ -1,9 +1,11 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode};
 #[allow(unused_imports)]
 use crossterm::ExecutableCommand;
 
+use super::keymap::KeyMap;
+
 // #[derive(Debug, Error)]
 // #[error("GetCommandError {0}" )]
 // pub struct InvalidMoveError(#[from] crossterm::ErrorKind);
@@ -14,8 +16,7 @@ use crossterm::ExecutableCommand;
 //     }
 // }
 
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Right,
@@ -24,14 +25,14 @@ pub enum Direction {
 }
 
 impl Direction {
-    pub fn is_opposite(&mut self, other: &Direction) -> bool {
-        match (self, other) {
-            (Direction::Up, Direction::Down) => true,
-            (Direction::Right, Direction::Left) => true,
-            (Direction::Down, Direction::Up) => true,
-            (Direction::Left, Direction::Right) => true,
-            _ => false,
-        }
+    pub fn is_opposite(&self, other: &Direction) -> bool {
+        matches!(
+            (self, other),
+            (Direction::Up, Direction::Down)
+                | (Direction::Right, Direction::Left)
+                | (Direction::Down, Direction::Up)
+                | (Direction::Left, Direction::Right)
+        )
     }
     // fn move_to(&mut self, other_direction: Direction) {
     //     if !self.is_opposite(&other_direction) {
@@ -40,60 +41,116 @@ impl Direction {
     // }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Command {
     Move(Direction),
+    Pause,
+    Resume,
+    Restart,
     Quit,
+    /// The terminal changed to this many columns and rows. Not bound to any
+    /// key.
+    Resize(u16, u16),
 }
 
-pub struct InvalidCommandError;
-
-impl TryFrom<KeyEvent> for Command {
-    type Error = InvalidCommandError;
-    fn try_from(key_event: KeyEvent) -> Result<Self, InvalidCommandError> {
-        match key_event.modifiers {
-            KeyModifiers::NONE => match key_event.code {
-                KeyCode::Char('q') => Ok(Command::Quit),
-                KeyCode::Char('j') => Ok(Command::Move(Direction::Down)),
-                KeyCode::Char('k') => Ok(Command::Move(Direction::Up)),
-                KeyCode::Char('h') => Ok(Command::Move(Direction::Left)),
-                KeyCode::Char('l') => Ok(Command::Move(Direction::Right)),
-                _ => Err(InvalidCommandError),
-            },
-            KeyModifiers::CONTROL => match key_event.code {
-                KeyCode::Char('c') => Ok(Command::Quit),
-                _ => Err(InvalidCommandError),
-            },
-            _ => Err(InvalidCommandError),
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Move(Direction::Up) => "up",
+            Command::Move(Direction::Down) => "down",
+            Command::Move(Direction::Left) => "left",
+            Command::Move(Direction::Right) => "right",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+            Command::Restart => "restart",
+            Command::Quit => "quit",
+            Command::Resize(..) => "resize",
         }
     }
-}
 
-impl Command {
-    fn get_latest_event() -> Option<Event> {
-        let mut event: Option<Event> = None;
+    /// Blocks until a key is pressed or the terminal is resized and returns
+    /// its command, if it has one.
+    pub fn wait(keys: &KeyMap) -> Result<Option<Self>, crossterm::ErrorKind> {
+        loop {
+            match event::read()? {
+                Event::Key(key_event) => return Ok(keys.get(key_event)),
+                Event::Resize(cols, rows) => return Ok(Some(Command::Resize(cols, rows))),
+                Event::Mouse(_) => continue,
+            }
+        }
+    }
+
+    /// Reads a line of at most `max_len` characters, calling `echo` after
+    /// every edit. Enter accepts, Esc gives up and returns `None`.
+    pub fn read_line(
+        max_len: usize,
+        mut echo: impl FnMut(&str) -> Result<(), crossterm::ErrorKind>,
+    ) -> Result<Option<String>, crossterm::ErrorKind> {
+        let mut line = String::new();
         loop {
-            if event::poll(Duration::from_millis(0)).ok()? == false {
-                break;
-            } else {
-                event = crossterm::event::read().ok();
+            if let Event::Key(key_event) = event::read()? {
+                match key_event.code {
+                    KeyCode::Enter => return Ok(Some(line)),
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Backspace => {
+                        line.pop();
+                    }
+                    KeyCode::Char(c) if !c.is_control() && line.chars().count() < max_len => {
+                        line.push(c)
+                    }
+                    _ => continue,
+                }
+                echo(&line)?;
             }
         }
-        event
     }
 
-    pub fn new() -> Option<Self> {
-        let latest_event = if let Some(event) = Command::get_latest_event() {
-            event
-        } else {
-            return None;
-        };
+    /// Every command typed since the last call, oldest first. Of the resizes
+    /// in between, e.g. from dragging the window, only the last one counts;
+    /// it is where the drag ended.
+    pub fn pending(keys: &KeyMap) -> Result<Vec<Self>, crossterm::ErrorKind> {
+        let mut commands = vec![];
+        while event::poll(Duration::from_millis(0))? {
+            match event::read()? {
+                Event::Key(key_event) => commands.extend(keys.get(key_event)),
+                Event::Resize(cols, rows) => {
+                    commands.retain(|command| !matches!(command, Command::Resize(..)));
+                    commands.push(Command::Resize(cols, rows));
+                }
+                Event::Mouse(_) => {}
+            }
+        }
+        Ok(commands)
+    }
+}
+
+/// How many turns can be typed ahead of the snake.
+const MAX_QUEUED_TURNS: usize = 3;
+
+/// Turns typed faster than the snake moves. One is applied per tick, so
+/// pressing up then left within a single tick makes both turns.
+#[derive(Default)]
+pub struct TurnQueue {
+    turns: VecDeque<Direction>,
+}
 
-        match latest_event {
-            Event::Key(key_event) => key_event.try_into().ok(),
-            _ => None
+impl TurnQueue {
+    /// Queues `dir` unless it repeats or reverses the direction the snake
+    /// will be heading in by then: the last queued turn, or `current`.
+    pub fn push(&mut self, dir: Direction, current: Direction) {
+        let heading = self.turns.back().copied().unwrap_or(current);
+        if self.turns.len() < MAX_QUEUED_TURNS && dir != heading && !heading.is_opposite(&dir) {
+            self.turns.push_back(dir);
         }
     }
+
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.turns.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
 }
 
 // if event::poll(timout)? {
@@ -104,3 +161,63 @@ impl Command {
 //     }
 // }
 // return Ok(*self);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(turns: &mut TurnQueue) -> Vec<Direction> {
+        std::iter::from_fn(|| turns.pop()).collect()
+    }
+
+    #[test]
+    fn two_turns_in_one_tick_both_count() {
+        let mut turns = TurnQueue::default();
+        turns.push(Direction::Left, Direction::Up);
+        turns.push(Direction::Down, Direction::Up);
+        assert_eq!(turns.pop(), Some(Direction::Left));
+        assert_eq!(turns.pop(), Some(Direction::Down));
+        assert_eq!(turns.pop(), None);
+    }
+
+    #[test]
+    fn turns_are_checked_against_the_last_queued_one() {
+        let mut turns = TurnQueue::default();
+        // Reverses and repeats of the current direction.
+        turns.push(Direction::Down, Direction::Up);
+        turns.push(Direction::Up, Direction::Up);
+        assert_eq!(drain(&mut turns), []);
+
+        turns.push(Direction::Left, Direction::Up);
+        // Reverses and repeats of the queued turn, not of `current`.
+        turns.push(Direction::Right, Direction::Up);
+        turns.push(Direction::Left, Direction::Up);
+        turns.push(Direction::Up, Direction::Up);
+        assert_eq!(drain(&mut turns), [Direction::Left, Direction::Up]);
+    }
+
+    #[test]
+    fn only_three_turns_wait() {
+        let mut turns = TurnQueue::default();
+        for dir in [
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ] {
+            turns.push(dir, Direction::Up);
+        }
+        assert_eq!(
+            drain(&mut turns),
+            [Direction::Left, Direction::Down, Direction::Right]
+        );
+    }
+
+    #[test]
+    fn clear_drops_every_turn() {
+        let mut turns = TurnQueue::default();
+        turns.push(Direction::Left, Direction::Up);
+        turns.clear();
+        assert_eq!(turns.pop(), None);
+    }
+}
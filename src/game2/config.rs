@@ -0,0 +1,59 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{
+    keymap::KeyBindings,
+    speed::{Difficulty, Speed},
+    theme::GlyphSupport,
+};
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("could not read {0}: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("{0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+}
+
+/// Everything that can be set in `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub difficulty: Difficulty,
+    /// Replaces the speed of the difficulty preset.
+    pub speed: Option<Speed>,
+    /// A built in theme, a theme in `snake-cli/themes` in the user's config
+    /// directory, even with `--config`, or a path to a theme file, relative
+    /// to the current directory.
+    pub theme: Option<String>,
+    /// What the font can show; guessed from the terminal when left out.
+    pub glyphs: Option<GlyphSupport>,
+    pub keys: KeyBindings,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake-cli").join("config.toml"))
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed.unwrap_or_else(|| self.difficulty.speed())
+    }
+
+    /// Reads the config file from the user's config directory. Not having one
+    /// is fine and means all defaults.
+    pub fn load() -> Result<Self, ConfigError> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load_from(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Reads the config file at `path`, which has to exist.
+    pub fn load_from(path: PathBuf) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(&path).map_err(|err| ConfigError::Read(path.clone(), err))?;
+        toml::from_str(&text).map_err(|err| ConfigError::Parse(path, err))
+    }
+}
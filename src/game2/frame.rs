@@ -0,0 +1,92 @@
+use crossterm::style::Color;
+use unicode_width::UnicodeWidthChar;
+
+/// One terminal cell: a character and the colors it is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Cell {
+    pub const BLANK: Cell = Cell {
+        symbol: ' ',
+        fg: Color::Reset,
+        bg: Color::Reset,
+    };
+
+    /// Stands in for the right half of a wide character in the cell to its
+    /// left.
+    pub const WIDE_TAIL: char = '\0';
+}
+
+/// How many columns `symbol` takes up, 0 for control characters.
+pub fn width(symbol: char) -> usize {
+    symbol.width().unwrap_or(0)
+}
+
+/// Whether `symbol` is in the private use area, where Nerd Fonts put their
+/// icons.
+pub fn is_private_use(symbol: char) -> bool {
+    ('\u{e000}'..='\u{f8ff}').contains(&symbol)
+}
+
+/// Whether a terminal is sure to move the cursor exactly one column for
+/// `symbol`. Private use characters, like Nerd Font icons, are drawn one or
+/// two columns wide depending on the font.
+pub fn is_narrow(symbol: char) -> bool {
+    width(symbol) == 1 && !is_private_use(symbol)
+}
+
+/// A picture of the whole terminal, one [`Cell`] per column and row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    size: (u16, u16),
+    cells: Vec<Cell>,
+}
+
+impl FrameBuffer {
+    /// A blank buffer of `size` columns and rows.
+    pub fn new(size: (u16, u16)) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::BLANK; size.0 as usize * size.1 as usize],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::BLANK);
+    }
+
+    fn index(&self, (c, r): (u16, u16)) -> Option<usize> {
+        (c < self.size.0 && r < self.size.1).then(|| r as usize * self.size.0 as usize + c as usize)
+    }
+
+    /// Sets the cell at `at`. Anything off the buffer is cut off.
+    pub fn set(&mut self, at: (u16, u16), cell: Cell) {
+        if let Some(i) = self.index(at) {
+            self.cells[i] = cell;
+        }
+    }
+
+    /// The cells, one row at a time.
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.size.0.max(1) as usize)
+    }
+
+    /// The cells that differ from `before`, row by row. Both buffers have to
+    /// be the same size.
+    pub fn diff<'a>(
+        &'a self,
+        before: &'a FrameBuffer,
+    ) -> impl Iterator<Item = ((u16, u16), Cell)> + 'a {
+        let n_cols = self.size.0.max(1) as usize;
+        self.cells
+            .iter()
+            .zip(&before.cells)
+            .enumerate()
+            .filter(|(_, (now, then))| now != then)
+            .map(move |(i, (&now, _))| (((i % n_cols) as u16, (i / n_cols) as u16), now))
+    }
+}
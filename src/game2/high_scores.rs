@@ -0,0 +1,150 @@
+use std::{cmp::Reverse, fmt, fs, io, path::PathBuf};
+
+/// How many entries each table keeps.
+pub const TABLE_SIZE: usize = 10;
+pub const NAME_LEN: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub mode: String,
+    pub board: (u16, u16),
+    pub name: String,
+    pub points: u32,
+}
+
+impl Entry {
+    /// One tab separated line: mode, board as `WIDTHxHEIGHT` in cells,
+    /// points, name.
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        let mode = fields.next()?.to_string();
+        let (width, height) = fields.next()?.split_once('x')?;
+        let board = (width.parse().ok()?, height.parse().ok()?);
+        let points = fields.next()?.parse().ok()?;
+        let name = fields.next()?.to_string();
+        Some(Self {
+            mode,
+            board,
+            name,
+            points,
+        })
+    }
+
+    fn is_in(&self, mode: &str, board: (u16, u16)) -> bool {
+        self.mode == mode && self.board == board
+    }
+}
+
+/// Top scores, one table per game mode and board size, kept in a file under
+/// the user's data directory.
+pub struct HighScores {
+    path: Option<PathBuf>,
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake-cli").join("scores.tsv"))
+    }
+
+    /// Reads the table file. A missing or unreadable file is an empty table,
+    /// so a broken scores file never keeps anyone from playing.
+    pub fn load() -> Self {
+        let path = Self::default_path();
+        let entries = path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().filter_map(Entry::parse).collect())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// The entries for `mode` on a `board`, best first.
+    pub fn table(&self, mode: &str, board: (u16, u16)) -> Vec<&Entry> {
+        let mut table: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_in(mode, board))
+            .collect();
+        table.sort_by_key(|entry| Reverse(entry.points));
+        table.truncate(TABLE_SIZE);
+        table
+    }
+
+    pub fn qualifies(&self, mode: &str, board: (u16, u16), points: u32) -> bool {
+        let table = self.table(mode, board);
+        points > 0
+            && (table.len() < TABLE_SIZE || table.last().is_some_and(|last| points > last.points))
+    }
+
+    pub fn insert(&mut self, entry: Entry) {
+        let (mode, board) = (entry.mode.clone(), entry.board);
+        self.entries.push(entry);
+        let kept: Vec<Entry> = self.table(&mode, board).into_iter().cloned().collect();
+        self.entries.retain(|entry| !entry.is_in(&mode, board));
+        self.entries.extend(kept);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = self.path.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory to keep scores in",
+            )
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}\t{}x{}\t{}\t{}\n",
+                    entry.mode, entry.board.0, entry.board.1, entry.points, entry.name
+                )
+            })
+            .collect();
+        fs::write(path, text)
+    }
+
+    /// The table as lines of text, for the game over screen.
+    pub fn lines(&self, mode: &str, board: (u16, u16)) -> Vec<String> {
+        self.table(mode, board)
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                format!(
+                    "{:>2}. {:<width$} {:>6}",
+                    i + 1,
+                    entry.name,
+                    entry.points,
+                    width = NAME_LEN
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tables: Vec<(&str, (u16, u16))> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.mode.as_str(), entry.board))
+            .collect();
+        tables.sort();
+        tables.dedup();
+
+        if tables.is_empty() {
+            return writeln!(f, "no high scores yet");
+        }
+        for (mode, board) in tables {
+            writeln!(f, "{} {}x{}", mode, board.0, board.1)?;
+            for line in self.lines(mode, board) {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::commands::{Command, Direction};
+
+#[derive(Error, Debug)]
+pub enum KeyMapError {
+    #[error("unknown key {0:?}")]
+    UnknownKey(String),
+    #[error("{0:?}: Shift only goes with letters, write the shifted character instead")]
+    ShiftedChar(String),
+    #[error("{0} needs at least one key")]
+    Unbound(&'static str),
+    #[error("{key:?} is bound to both {first} and {second}")]
+    Conflict {
+        key: String,
+        first: &'static str,
+        second: &'static str,
+    },
+}
+
+/// The `[keys]` table of the config file. Each action lists every key that
+/// triggers it; actions left out keep their default keys.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBindings {
+    pub up: Option<Vec<String>>,
+    pub down: Option<Vec<String>>,
+    pub left: Option<Vec<String>>,
+    pub right: Option<Vec<String>>,
+    pub pause: Option<Vec<String>>,
+    pub resume: Option<Vec<String>>,
+    pub restart: Option<Vec<String>>,
+    pub quit: Option<Vec<String>>,
+}
+
+/// An action, the keys configured for it and the keys it has by default.
+type Action<'a> = (Command, &'a Option<Vec<String>>, &'static [&'static str]);
+
+impl KeyBindings {
+    /// Every action with its configured keys and its defaults: vim keys,
+    /// WASD and the arrows for moving.
+    fn actions(&self) -> [Action<'_>; 8] {
+        [
+            (Command::Move(Direction::Up), &self.up, &["k", "w", "Up"]),
+            (
+                Command::Move(Direction::Down),
+                &self.down,
+                &["j", "s", "Down"],
+            ),
+            (
+                Command::Move(Direction::Left),
+                &self.left,
+                &["h", "a", "Left"],
+            ),
+            (
+                Command::Move(Direction::Right),
+                &self.right,
+                &["l", "d", "Right"],
+            ),
+            (Command::Pause, &self.pause, &["p", "Space"]),
+            (Command::Resume, &self.resume, &["Enter"]),
+            (Command::Restart, &self.restart, &["r"]),
+            (Command::Quit, &self.quit, &["q", "Ctrl-c"]),
+        ]
+    }
+}
+
+/// Parses names like `k`, `Up`, `Space`, `F5` or `Ctrl-c`. `Shift-a` is
+/// the same key as `A`; other shifted characters are written as they come
+/// out, e.g. `!` rather than `Shift-1`.
+fn parse_key(name: &str) -> Result<KeyEvent, KeyMapError> {
+    let unknown = || KeyMapError::UnknownKey(name.to_string());
+
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((modifier, key)) = rest.split_once('-') {
+        if key.is_empty() {
+            break;
+        }
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(unknown()),
+        };
+        rest = key;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+            if !c.is_alphabetic() {
+                return Err(KeyMapError::ShiftedChar(name.to_string()));
+            }
+            KeyCode::Char(c.to_uppercase().next().unwrap())
+        }
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(unknown()),
+            },
+        },
+    };
+    Ok(normalize(KeyEvent::new(code, modifiers)))
+}
+
+/// Terminals report shift with the already shifted character, so it carries
+/// no extra meaning for character keys.
+fn normalize(key_event: KeyEvent) -> KeyEvent {
+    match key_event.code {
+        KeyCode::Char(_) => {
+            KeyEvent::new(key_event.code, key_event.modifiers - KeyModifiers::SHIFT)
+        }
+        _ => key_event,
+    }
+}
+
+/// Which command each key triggers.
+pub struct KeyMap {
+    bindings: HashMap<KeyEvent, Command>,
+    names: Vec<(Command, Vec<String>)>,
+}
+
+impl KeyMap {
+    pub fn new(config: &KeyBindings) -> Result<Self, KeyMapError> {
+        let mut bindings = HashMap::new();
+        let mut names = vec![];
+        for (command, keys, defaults) in config.actions() {
+            let keys: Vec<String> = match keys {
+                Some(keys) => keys.clone(),
+                None => defaults.iter().map(|key| key.to_string()).collect(),
+            };
+            // Raw mode swallows Ctrl-c, so without these there is no playing
+            // or no way out.
+            if keys.is_empty() && matches!(command, Command::Move(_) | Command::Quit) {
+                return Err(KeyMapError::Unbound(command.name()));
+            }
+            for key in &keys {
+                if let Some(first) = bindings.insert(parse_key(key)?, command) {
+                    if first != command {
+                        return Err(KeyMapError::Conflict {
+                            key: key.clone(),
+                            first: first.name(),
+                            second: command.name(),
+                        });
+                    }
+                }
+            }
+            names.push((command, keys));
+        }
+        Ok(Self { bindings, names })
+    }
+
+    pub fn get(&self, key_event: KeyEvent) -> Option<Command> {
+        self.bindings.get(&normalize(key_event)).copied()
+    }
+
+    /// The keys bound to `command` as written in the config, e.g. `p/Space`.
+    pub fn describe(&self, command: Command) -> String {
+        self.names
+            .iter()
+            .find(|(bound, _)| *bound == command)
+            .map(|(_, keys)| keys.join("/"))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(toml: &str) -> KeyBindings {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn parses_key_names() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        assert_eq!(
+            parse_key("Ctrl-c").unwrap(),
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_key("Shift-a").unwrap(),
+            key(KeyCode::Char('A'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_key("F12").unwrap(),
+            key(KeyCode::F(12), KeyModifiers::NONE)
+        );
+        assert!(matches!(
+            parse_key("Shift-1"),
+            Err(KeyMapError::ShiftedChar(_))
+        ));
+        assert!(matches!(parse_key("F13"), Err(KeyMapError::UnknownKey(_))));
+        assert!(matches!(
+            parse_key("Hyper-k"),
+            Err(KeyMapError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn shift_matches_the_uppercase_letter() {
+        let keys = KeyMap::new(&bindings("up = [\"Shift-k\"]")).unwrap();
+        let typed = KeyEvent::new(KeyCode::Char('K'), KeyModifiers::SHIFT);
+        assert_eq!(keys.get(typed), Some(Command::Move(Direction::Up)));
+    }
+
+    #[test]
+    fn a_key_cannot_trigger_two_actions() {
+        let err = KeyMap::new(&bindings("quit = [\"k\"]")).err().unwrap();
+        assert!(matches!(
+            err,
+            KeyMapError::Conflict {
+                first: "up",
+                second: "quit",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn quit_and_moves_need_a_key() {
+        for action in ["quit", "up", "left"] {
+            let err = KeyMap::new(&bindings(&format!("{} = []", action)))
+                .err()
+                .unwrap();
+            assert!(matches!(err, KeyMapError::Unbound(name) if name == action));
+        }
+        assert!(KeyMap::new(&bindings("resume = []")).is_ok());
+    }
+}
@@ -1,115 +1,339 @@
+mod board;
 mod candy_spawner;
+mod colors;
 mod commands;
+mod config;
+mod frame;
+mod high_scores;
+mod keymap;
+mod mode;
 mod render;
+mod score;
+mod screen;
 mod snake;
-use crate::game2::snake::Snake;
+mod speed;
+mod state;
+mod theme;
 
 use thiserror::Error;
 
-use crossterm::{cursor, style, ExecutableCommand};
-use std::{
-    sync::{mpsc, Arc, Mutex},
-    thread, collections::BTreeSet
-};
+use std::{fs, path::PathBuf, thread};
+
+use crossterm::terminal;
 
 use crate::terminal::Terminal;
 
-use self::candy_spawner::CandySpawner;
+pub use self::{
+    board::{Board, BoardError, Dimension},
+    colors::ColorSupport,
+    config::Config,
+    high_scores::HighScores,
+    keymap::KeyMap,
+    mode::Mode,
+    speed::{Difficulty, Speed},
+    theme::{GlyphSupport, Theme, DEFAULT_THEME},
+};
+
+use self::{
+    commands::{Command, TurnQueue},
+    high_scores::{Entry, NAME_LEN},
+    screen::Screen,
+    state::{Event, GameState},
+};
+
+pub struct Settings {
+    /// Seeds candy placement; the same seed and inputs replay the same game.
+    /// Without one every round draws a fresh seed.
+    pub seed: Option<u64>,
+    pub mode: Mode,
+    pub keys: KeyMap,
+    pub speed: Speed,
+    pub theme: Theme,
+    /// Where to write the board as text when a round ends.
+    pub snapshot: Option<PathBuf>,
+}
 
 pub struct Game {
     term: Terminal,
+    settings: Settings,
 }
 
-#[derive(Error, Debug)]
-#[error("hit the wall")]
-pub struct InvalidMoveError;
+enum RoundEnd {
+    Restart,
+    Quit,
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidMoveError {
+    #[error("hit the wall")]
+    HitWall,
+    #[error("bit its own tail")]
+    HitSelf,
+}
 
 impl Game {
-    pub fn new(term: Terminal) -> Self {
-        Self { term }
-    }
-    fn render_background(&mut self) -> Result<(), crossterm::ErrorKind> {
-        self.term
-            .stdout
-            .lock()
-            .unwrap()
-            .execute(style::SetForegroundColor(self.term.color))?;
-        for col in 1..self.term.n_cols() / 2 {
-            for row in 1..self.term.n_rows() {
-                self.term
-                    .stdout
-                    .lock()
-                    .unwrap()
-                    .execute(cursor::MoveTo(col * 2, row))?
-                    .execute(style::Print(" "))?
-                    .execute(cursor::MoveRight(1))?
-                    .execute(style::Print(" "))?;
-            }
-        }
-        Ok(())
+    pub fn new(term: Terminal, settings: Settings) -> Self {
+        Self { term, settings }
     }
 
-    fn render_frame(&mut self) {
-        let (n, m) = self.term.size;
-
-        (1..m * 2)
-            .map(|c| ((c, 0), '═'))
-            .chain((1..m * 2).map(|c| ((c, n), '═')))
-            .chain((1..n).map(|r| ((0, r), '║')))
-            .chain((1..n).map(|r| ((m * 2, r), '║')))
-            .chain(vec![
-                ((0, 0), '╔'),
-                ((0, n), '╚'),
-                ((m * 2, 0), '╗'),
-                ((m * 2, n), '╝'),
-            ])
-            .for_each(|((c, r), symbol)| {
-                self.term
-                    .stdout
-                    .lock()
-                    .unwrap()
-                    .execute(cursor::MoveTo(c, r))
-                    .unwrap()
-                    .execute(style::Print(symbol))
-                    .unwrap();
-            })
+    pub fn run(&mut self) -> Result<(), crossterm::ErrorKind> {
+        let _guard = self.term.initialize()?;
+        let mut screen = Screen::new(&self.term, self.settings.theme.clone());
+        while let RoundEnd::Restart = self.play_round(&mut screen)? {}
+        Ok(())
     }
 
-    pub fn run(&mut self) -> Result<(), crossterm::ErrorKind> {
-        let (sender, receiver) = mpsc::channel();
+    fn play_round(&self, screen: &mut Screen) -> Result<RoundEnd, crossterm::ErrorKind> {
+        // Every round is laid out afresh, so restarting after a resize plays
+        // on a board sized for the new terminal.
+        if let Some(end) = self.lay_out(screen, |size| self.term.layout(size), terminal::size()?)? {
+            return Ok(end);
+        }
+        let seed = self.settings.seed.unwrap_or_else(rand::random);
+        let mut state = GameState::new(
+            screen.board().cells(),
+            self.settings.mode,
+            seed,
+            self.settings.speed,
+        );
+        screen.render_background();
+        screen.render_frame();
+        if let Some(event) = state.spawn_candy() {
+            screen.draw(&event, &state.snake);
+        }
+        screen.render_hud(&state);
+        screen.flush()?;
 
-        self.term.initialize();
-        self.render_background().unwrap();
-        self.render_frame();
+        let mut turns = TurnQueue::default();
+        let game_over = loop {
+            for command in Command::pending(&self.settings.keys)? {
+                match command {
+                    Command::Move(new_dir) => turns.push(new_dir, state.snake.dir),
+                    Command::Pause => {
+                        if let Some(end) = self.pause(screen, &state)? {
+                            return Ok(end);
+                        }
+                        // Anything typed before the pause, queued turns
+                        // included, is stale by now.
+                        turns.clear();
+                        break;
+                    }
+                    Command::Restart => return Ok(RoundEnd::Restart),
+                    Command::Quit => return Ok(RoundEnd::Quit),
+                    Command::Resize(cols, rows) => {
+                        if let Some(end) = self.resized(screen, &state, (cols, rows))? {
+                            return Ok(end);
+                        }
+                        if let Some(end) = self.pause(screen, &state)? {
+                            return Ok(end);
+                        }
+                        turns.clear();
+                        break;
+                    }
+                    Command::Resume => {}
+                }
+            }
 
-        let mut candy_list = Arc::new(Mutex::new(BTreeSet::<(u16, u16)>::new()));
+            let events = state.step(turns.pop());
+            for event in &events {
+                screen.draw(event, &state.snake);
+            }
+            screen.render_hud(&state);
+            screen.flush()?;
+            let game_over = events.iter().find_map(|event| match event {
+                Event::Died(reason) => Some(("GAME OVER", format!("you {}", reason))),
+                Event::BoardFull => Some(("YOU WIN", "the board is full".to_string())),
+                _ => None,
+            });
+            if let Some((title, message)) = game_over {
+                break (title, message);
+            }
 
-        let mut snake = Snake::new(self.term.size, Arc::clone(&mut self.term.stdout));
+            thread::sleep(state.tick);
+        };
 
-        let mut candy_spawner = CandySpawner::new(
-            self.term.size,
-            Arc::clone(&mut self.term.stdout),
-            Arc::clone(&mut candy_list),
-        );
+        let (title, message) = game_over;
+        self.show_game_over(screen, &state, seed, title, message)
+    }
 
-        // let candylist = Arc::clone(&mut candy_spawner.locs);
+    /// Centers the board on a terminal of `size`, as `fit` sizes it. While
+    /// it does not fit, says so and waits for the terminal to grow, or for
+    /// the player to quit. Restarting is offered when a new round would fit
+    /// at the new size, which it may on a board sized in percent.
+    fn lay_out(
+        &self,
+        screen: &mut Screen,
+        fit: impl Fn((u16, u16)) -> Result<Board, BoardError>,
+        mut size: (u16, u16),
+    ) -> Result<Option<RoundEnd>, crossterm::ErrorKind> {
+        let keys = &self.settings.keys;
+        loop {
+            screen.reset(size)?;
+            let err = match fit(size) {
+                Ok(board) => {
+                    screen.set_board(board);
+                    return Ok(None);
+                }
+                Err(err) => err,
+            };
+            let restartable = self.term.layout(size).is_ok();
+            let quit = keys.describe(Command::Quit);
+            let options = if restartable {
+                let restart = keys.describe(Command::Restart);
+                format!("{} to restart at this size, {} to quit", restart, quit)
+            } else {
+                format!("make the terminal bigger, or {} to quit", quit)
+            };
+            screen.render_notice(&["TERMINAL TOO SMALL".to_string(), err.to_string(), options]);
+            screen.flush()?;
+            size = loop {
+                match Command::wait(keys)? {
+                    Some(Command::Resize(cols, rows)) => break (cols, rows),
+                    Some(Command::Restart) if restartable => return Ok(Some(RoundEnd::Restart)),
+                    Some(Command::Quit) => return Ok(Some(RoundEnd::Quit)),
+                    _ => continue,
+                }
+            };
+        }
+    }
 
-        let candy_thread = thread::spawn(move || candy_spawner.run(receiver));
-        let snake_thread = thread::spawn(move || snake.run(Arc::clone(&mut candy_list)));
+    /// Keeps the round's board on a terminal that changed to `size` and
+    /// redraws it there.
+    fn resized(
+        &self,
+        screen: &mut Screen,
+        state: &GameState,
+        size: (u16, u16),
+    ) -> Result<Option<RoundEnd>, crossterm::ErrorKind> {
+        let board = screen.board();
+        let end = self.lay_out(screen, |size| board.refit(size), size)?;
+        if end.is_none() {
+            screen.render_state(state);
+        }
+        Ok(end)
+    }
 
-        match snake_thread.join() {
-            Ok(_) => println!("snake thread successfully exited"),
-            Err(_) => println!("snake thread exit failed!!")
+    /// Freezes the round, snake and candy alike, until the player resumes,
+    /// restarts or quits.
+    fn pause(
+        &self,
+        screen: &mut Screen,
+        state: &GameState,
+    ) -> Result<Option<RoundEnd>, crossterm::ErrorKind> {
+        let keys = &self.settings.keys;
+        let message = [
+            "PAUSED".to_string(),
+            String::new(),
+            format!(
+                "{} or {} to resume",
+                keys.describe(Command::Pause),
+                keys.describe(Command::Resume)
+            ),
+            format!(
+                "{} to restart, {} to quit",
+                keys.describe(Command::Restart),
+                keys.describe(Command::Quit)
+            ),
+        ];
+        screen.render_message(&message);
+        screen.flush()?;
+        let end = loop {
+            match Command::wait(keys)? {
+                Some(Command::Pause) | Some(Command::Resume) => break None,
+                Some(Command::Restart) => break Some(RoundEnd::Restart),
+                Some(Command::Quit) => break Some(RoundEnd::Quit),
+                Some(Command::Resize(cols, rows)) => {
+                    if let Some(end) = self.resized(screen, state, (cols, rows))? {
+                        break Some(end);
+                    }
+                    screen.render_message(&message);
+                    screen.flush()?;
+                }
+                Some(Command::Move(_)) | None => continue,
+            }
         };
-        sender.send("Stop").unwrap();
+        if end.is_none() {
+            screen.render_state(state);
+            screen.flush()?;
+        }
+        Ok(end)
+    }
 
-        self.term.reset();
-        match candy_thread.join() {
-            Ok(_) => println!("candy thread successfully exited"),
-            Err(_) => println!("candy thread exit failed!!")
-        };
+    /// Shows how the round ended, asks for a name if the score made it into
+    /// the high score table, then shows the table.
+    fn show_game_over(
+        &self,
+        screen: &mut Screen,
+        state: &GameState,
+        seed: u64,
+        title: &str,
+        message: String,
+    ) -> Result<RoundEnd, crossterm::ErrorKind> {
+        let points = state.score.points;
+        let mode = state.mode.name();
+        let mut lines = vec![
+            title.to_string(),
+            message,
+            format!("score {}", points),
+            format!("seed {}", seed),
+            String::new(),
+        ];
 
+        if let Some(path) = &self.settings.snapshot {
+            let written = screen
+                .snapshot(state)
+                .and_then(|text| Ok(fs::write(path, text)?));
+            if let Err(err) = written {
+                lines.push(format!("could not save snapshot: {}", err));
+            }
+        }
 
-        Ok(())
+        let mut scores = HighScores::load();
+        if scores.qualifies(mode, state.board, points) {
+            lines.push("new high score! your name:".to_string());
+            lines.push(String::new());
+            screen.render_message(&lines);
+            screen.flush()?;
+            let name = Command::read_line(NAME_LEN, |name| {
+                *lines.last_mut().unwrap() = name.to_string();
+                screen.render_message(&lines);
+                screen.flush()
+            })?;
+            lines.truncate(lines.len() - 2);
+
+            if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+                scores.insert(Entry {
+                    mode: mode.to_string(),
+                    board: state.board,
+                    name,
+                    points,
+                });
+                if let Err(err) = scores.save() {
+                    lines.push(format!("could not save scores: {}", err));
+                }
+            }
+        }
+
+        lines.extend(scores.lines(mode, state.board));
+        lines.push(String::new());
+        lines.push(format!(
+            "{} to play again, any other key to quit",
+            self.settings.keys.describe(Command::Restart)
+        ));
+        screen.render_message(&lines);
+        screen.flush()?;
+        loop {
+            match Command::wait(&self.settings.keys)? {
+                Some(Command::Resize(cols, rows)) => {
+                    if let Some(end) = self.resized(screen, state, (cols, rows))? {
+                        return Ok(end);
+                    }
+                    screen.render_message(&lines);
+                    screen.flush()?;
+                }
+                Some(Command::Restart) => return Ok(RoundEnd::Restart),
+                _ => return Ok(RoundEnd::Quit),
+            }
+        }
     }
 }
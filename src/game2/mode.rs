@@ -0,0 +1,33 @@
+use std::str::FromStr;
+
+/// What happens at the edge of the board. High scores are kept apart per
+/// mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Running into the frame ends the round.
+    #[default]
+    Classic,
+    /// Leaving the board on one side comes back in on the other.
+    Wrap,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::Wrap => "wrap",
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "classic" => Ok(Mode::Classic),
+            "wrap" => Ok(Mode::Wrap),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,3 +1,137 @@
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crossterm::{cursor, style, terminal, ExecutableCommand, QueueableCommand};
+
+use super::frame::{self, Cell, FrameBuffer};
+
+/// Where a [`Screen`](super::screen::Screen) sends its frames.
 pub trait Render {
-    fn render(cell: (u16, u16)) -> Result<(), crossterm::ErrorKind>;
+    /// Starts over on a blank output of `size` columns and rows.
+    fn clear(&mut self, size: (u16, u16)) -> Result<(), crossterm::ErrorKind>;
+
+    /// Shows `frame` on an output that currently shows `before`.
+    fn render(
+        &mut self,
+        frame: &FrameBuffer,
+        before: &FrameBuffer,
+    ) -> Result<(), crossterm::ErrorKind>;
+}
+
+/// Draws on a terminal, sending only the cells that changed.
+pub struct Crossterm<W: Write = io::Stdout> {
+    out: W,
+}
+
+impl<W: Write> Crossterm<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> Render for Crossterm<W> {
+    fn clear(&mut self, _size: (u16, u16)) -> Result<(), crossterm::ErrorKind> {
+        // Some terminals fill cleared cells with the current background,
+        // which would leave the last cell's color all over the screen.
+        self.out
+            .queue(style::ResetColor)?
+            .execute(terminal::Clear(terminal::ClearType::All))?;
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        frame: &FrameBuffer,
+        before: &FrameBuffer,
+    ) -> Result<(), crossterm::ErrorKind> {
+        let mut cursor = None;
+        let mut fg = None;
+        let mut bg = None;
+        for ((c, r), cell) in frame.diff(before) {
+            // Drawn along with the wide character on its left.
+            if cell.symbol == Cell::WIDE_TAIL {
+                continue;
+            }
+            if cursor != Some((c, r)) {
+                self.out.queue(cursor::MoveTo(c, r))?;
+            }
+            if fg != Some(cell.fg) {
+                self.out.queue(style::SetForegroundColor(cell.fg))?;
+                fg = Some(cell.fg);
+            }
+            if bg != Some(cell.bg) {
+                self.out.queue(style::SetBackgroundColor(cell.bg))?;
+                bg = Some(cell.bg);
+            }
+            self.out.queue(style::Print(cell.symbol))?;
+            // Where the cursor ends up after anything else is up to the
+            // terminal, so the next cell gets an explicit move.
+            cursor = frame::is_narrow(cell.symbol).then_some((c + 1, r));
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Keeps the last frame in memory as plain text, without colors. Its
+/// `Display` gives one line per row with trailing blanks cut off.
+#[derive(Default)]
+pub struct TextGrid {
+    frame: Option<FrameBuffer>,
+}
+
+impl Render for TextGrid {
+    fn clear(&mut self, size: (u16, u16)) -> Result<(), crossterm::ErrorKind> {
+        self.frame = Some(FrameBuffer::new(size));
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        frame: &FrameBuffer,
+        _before: &FrameBuffer,
+    ) -> Result<(), crossterm::ErrorKind> {
+        self.frame = Some(frame.clone());
+        Ok(())
+    }
+}
+
+impl fmt::Display for TextGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.frame.iter().flat_map(FrameBuffer::rows) {
+            let line: String = row
+                .iter()
+                .map(|cell| cell.symbol)
+                .filter(|&symbol| symbol != Cell::WIDE_TAIL)
+                .collect();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+
+    #[test]
+    fn clearing_resets_colors_first() {
+        let mut frame = FrameBuffer::new((2, 1));
+        let cell = Cell {
+            symbol: 'x',
+            fg: Color::Red,
+            bg: Color::Blue,
+        };
+        frame.set((0, 0), cell);
+        let mut backend = Crossterm::new(Vec::new());
+        backend.render(&frame, &FrameBuffer::new((2, 1))).unwrap();
+        backend.out.clear();
+
+        backend.clear((2, 1)).unwrap();
+        let out = String::from_utf8(backend.out).unwrap();
+        assert_eq!(out, "\x1b[0m\x1b[2J");
+    }
 }
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+const CANDY_POINTS: u32 = 10;
+const MAX_TIME_BONUS: u32 = 10;
+/// Eating within this long of the previous candy (or of the start) earns a
+/// bonus that shrinks linearly to nothing.
+const TIME_BONUS_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default)]
+pub struct Score {
+    pub points: u32,
+    pub candies: u32,
+    pub elapsed: Duration,
+    since_candy: Duration,
+}
+
+impl Score {
+    pub fn tick(&mut self, dt: Duration) {
+        self.elapsed += dt;
+        self.since_candy += dt;
+    }
+
+    fn time_bonus(&self) -> u32 {
+        let left = TIME_BONUS_WINDOW.saturating_sub(self.since_candy);
+        (MAX_TIME_BONUS as f64 * left.as_secs_f64() / TIME_BONUS_WINDOW.as_secs_f64()).round()
+            as u32
+    }
+
+    pub fn eat(&mut self) {
+        self.points += CANDY_POINTS + self.time_bonus();
+        self.candies += 1;
+        self.since_candy = Duration::ZERO;
+    }
+}
@@ -0,0 +1,434 @@
+use crossterm::style::Color;
+
+use super::{
+    board::{Board, Position, HUD_ROWS},
+    commands::Direction,
+    frame::{self, Cell, FrameBuffer},
+    render::{Crossterm, Render, TextGrid},
+    snake::{heading, Snake},
+    state::{Event, GameState},
+    theme::Theme,
+};
+use crate::terminal::Terminal;
+
+/// Message boxes are at least this wide so that redrawing one while it
+/// changes, e.g. during name entry, covers the previous text.
+const MESSAGE_WIDTH: usize = 28;
+
+/// Draws [`Event`]s coming out of the game state onto a [`Render`] backend,
+/// the terminal unless told otherwise. This is the only thing that writes to
+/// stdout while a game runs. Drawing only changes the back buffer;
+/// [`Screen::flush`] then hands the frame to the backend.
+pub struct Screen<R: Render = Crossterm> {
+    board: Board,
+    theme: Theme,
+    backend: R,
+    /// What the terminal will show after the next flush.
+    back: FrameBuffer,
+    /// What the terminal shows now.
+    front: FrameBuffer,
+}
+
+impl Screen {
+    /// Draws on stdout. Starts out empty; [`Screen::reset`] sizes it before
+    /// anything is drawn.
+    pub fn new(term: &Terminal, theme: Theme) -> Self {
+        Self::with_backend(term.board, theme, Crossterm::new(std::io::stdout()))
+    }
+}
+
+impl<R: Render> Screen<R> {
+    pub fn with_backend(board: Board, theme: Theme, backend: R) -> Self {
+        Self {
+            board,
+            theme,
+            backend,
+            back: FrameBuffer::new((0, 0)),
+            front: FrameBuffer::new((0, 0)),
+        }
+    }
+
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    /// `state` drawn as plain text, status line included.
+    pub fn snapshot(&self, state: &GameState) -> Result<String, crossterm::ErrorKind> {
+        let board = Board {
+            origin: (0, 0),
+            ..self.board
+        };
+        let (n_cols, n_rows) = board.frame_size();
+        let mut screen = Screen::with_backend(board, self.theme.clone(), TextGrid::default());
+        // Wide enough for the status line on narrow boards.
+        screen.reset(((n_cols + 1).max(80), n_rows + 1 + HUD_ROWS))?;
+        screen.render_state(state);
+        screen.flush()?;
+        Ok(screen.backend.to_string())
+    }
+
+    /// Moves drawing over to `board`, e.g. after the terminal was resized.
+    pub fn set_board(&mut self, board: Board) {
+        self.board = board;
+    }
+
+    /// Blanks the output and both buffers, sized for `size` columns and
+    /// rows.
+    pub fn reset(&mut self, size: (u16, u16)) -> Result<(), crossterm::ErrorKind> {
+        self.back = FrameBuffer::new(size);
+        self.front = FrameBuffer::new(size);
+        self.backend.clear(size)
+    }
+
+    /// Hands what was drawn since the last flush to the backend.
+    pub fn flush(&mut self) -> Result<(), crossterm::ErrorKind> {
+        self.backend.render(&self.back, &self.front)?;
+        self.front.clone_from(&self.back);
+        Ok(())
+    }
+
+    /// Lines of text in the top left corner of the terminal, for when there
+    /// is no room for the board to put them on.
+    pub fn render_notice(&mut self, lines: &[String]) {
+        let fg = self.theme.colors.text;
+        for (row, line) in lines.iter().enumerate() {
+            self.print((0, row as u16), line, fg, Color::Reset);
+        }
+    }
+
+    /// Puts `text` into the back buffer from `(c, r)` on. Wide characters
+    /// take up as many cells as they take columns.
+    fn print(&mut self, (c, r): (u16, u16), text: &str, fg: Color, bg: Color) {
+        let mut col = c;
+        for symbol in text.chars() {
+            let width = frame::width(symbol) as u16;
+            if width == 0 {
+                continue;
+            }
+            self.back.set((col, r), Cell { symbol, fg, bg });
+            for tail in 1..width {
+                let cell = Cell {
+                    symbol: Cell::WIDE_TAIL,
+                    fg,
+                    bg,
+                };
+                self.back.set((col.saturating_add(tail), r), cell);
+            }
+            col = col.saturating_add(width);
+        }
+    }
+
+    /// Prints `text` at `(c, r)`, relative to the top left corner of the
+    /// frame.
+    fn print_at(&mut self, (c, r): (u16, u16), text: &str, fg: Color, bg: Color) {
+        let at = self.board.to_screen((c, r));
+        self.print(at, text, fg, bg);
+    }
+
+    /// Fills the cell at `pos` with `symbol` on the board's background. The
+    /// columns `symbol` leaves free are padding, or a line joining up with
+    /// the cell to the right when a snake segment runs that way. On a wrap
+    /// board, that cell can be across the edge; nothing joins up with it.
+    fn render_cell(&mut self, pos: Position, symbol: char, fg: Color, joins_right: bool) {
+        let padding = if joins_right && pos.x + 1 < self.board.width {
+            self.theme.glyphs.body.horizontal
+        } else {
+            ' '
+        };
+        let padding_width = (self.board.cell_width as usize).saturating_sub(frame::width(symbol));
+        let text: String = std::iter::once(symbol)
+            .chain(std::iter::repeat_n(padding, padding_width))
+            .collect();
+        let bg = self.theme.colors.background;
+        self.print_at(self.board.project(pos), &text, fg, bg);
+    }
+
+    pub fn render_background(&mut self) {
+        let (width, height) = self.board.cells();
+        for y in 0..height {
+            for x in 0..width {
+                self.render_cell(Position::new(x, y), ' ', Color::Reset, false);
+            }
+        }
+    }
+
+    pub fn render_frame(&mut self) {
+        let (m, n) = self.board.frame_size();
+        let lines = self.theme.glyphs.border;
+
+        let frame: Vec<((u16, u16), char)> = (1..m)
+            .map(|c| ((c, 0), lines.horizontal))
+            .chain((1..m).map(|c| ((c, n), lines.horizontal)))
+            .chain((1..n).map(|r| ((0, r), lines.vertical)))
+            .chain((1..n).map(|r| ((m, r), lines.vertical)))
+            .chain(vec![
+                ((0, 0), lines.top_left),
+                ((0, n), lines.bottom_left),
+                ((m, 0), lines.top_right),
+                ((m, n), lines.bottom_right),
+            ])
+            .collect();
+        let fg = self.theme.colors.frame;
+        for (cell, symbol) in frame {
+            self.print_at(cell, &symbol.to_string(), fg, Color::Reset);
+        }
+    }
+
+    /// The status line under the frame.
+    pub fn render_hud(&mut self, state: &GameState) {
+        let (n_cols, n_rows) = self.board.frame_size();
+        let score = &state.score;
+        let seconds = score.elapsed.as_secs();
+        let hud = format!(
+            "score {}  length {}  speed {:.1}/s  time {:02}:{:02}",
+            score.points,
+            state.snake.len(),
+            1.0 / state.tick.as_secs_f64(),
+            seconds / 60,
+            seconds % 60,
+        );
+        let fg = self.theme.colors.text;
+        self.print_at(
+            (0, n_rows + 1),
+            &format!("{:<width$}", hud, width = n_cols as usize + 1),
+            fg,
+            Color::Reset,
+        );
+    }
+
+    /// A centered block of text over the board, like the game over screen.
+    pub fn render_message(&mut self, lines: &[String]) {
+        let (n_cols, n_rows) = self.board.frame_size();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(MESSAGE_WIDTH)
+            + 2;
+        let col = (n_cols / 2).saturating_sub(width as u16 / 2);
+        let top = (n_rows / 2).saturating_sub(lines.len() as u16 / 2);
+        let fg = self.theme.colors.text;
+        for (i, line) in lines.iter().enumerate() {
+            self.print_at(
+                (col, top + i as u16),
+                &format!("{:^width$}", line, width = width),
+                fg,
+                Color::Reset,
+            );
+        }
+    }
+
+    fn render_candy(&mut self, pos: Position) {
+        let (candy, fg) = (self.theme.glyphs.candy, self.theme.colors.candy);
+        self.render_cell(pos, candy, fg, false);
+    }
+
+    /// The head at `pos` pointing `dir`, joined to the body behind it.
+    fn render_head(&mut self, pos: Position, dir: Direction) {
+        let (head, fg) = (self.theme.glyphs.head.get(dir), self.theme.colors.head);
+        self.render_cell(pos, head, fg, dir == Direction::Left);
+    }
+
+    /// The body segment at `pos` that the snake entered heading `prev_dir`
+    /// and left heading `dir`.
+    fn render_body(&mut self, pos: Position, prev_dir: Direction, dir: Direction, fg: Color) {
+        let symbol = self.body_glyph(prev_dir, dir);
+        self.render_cell(pos, symbol, fg, Self::joins_right(prev_dir, dir));
+    }
+
+    /// The tail at `pos`, pointing `dir` where the body goes on.
+    fn render_tail(&mut self, pos: Position, dir: Direction, fg: Color) {
+        let tail = self.theme.glyphs.tail.get(dir);
+        self.render_cell(pos, tail, fg, dir == Direction::Right);
+    }
+
+    /// Every segment of `snake`, head to tail.
+    fn render_snake(&mut self, snake: &Snake) {
+        let cells: Vec<Position> = snake.cells().copied().collect();
+        let len = cells.len();
+        for (i, &cell) in cells.iter().enumerate().skip(1) {
+            let fg = self.theme.colors.body_at(i, len);
+            let dir = heading(cell, cells[i - 1]);
+            match cells.get(i + 1) {
+                Some(&behind) => self.render_body(cell, heading(behind, cell), dir, fg),
+                None => self.render_tail(cell, dir, fg),
+            }
+        }
+        self.render_head(snake.head(), snake.dir);
+    }
+
+    /// Draws the whole board from scratch, e.g. after an overlay covered it.
+    pub fn render_state(&mut self, state: &GameState) {
+        self.back.clear();
+        self.render_background();
+        self.render_frame();
+        for &candy in &state.candies {
+            self.render_candy(candy);
+        }
+        self.render_snake(&state.snake);
+        self.render_hud(state);
+    }
+
+    /// Whether the segment the snake enters heading `prev_dir` and leaves
+    /// heading `dir` connects to the cell on its right.
+    fn joins_right(prev_dir: Direction, dir: Direction) -> bool {
+        prev_dir == Direction::Left || dir == Direction::Right
+    }
+
+    fn body_glyph(&self, prev_dir: Direction, curr_dir: Direction) -> char {
+        let lines = self.theme.glyphs.body;
+        match prev_dir {
+            Direction::Up => match curr_dir {
+                Direction::Up => lines.vertical,
+                Direction::Down => lines.vertical,
+                Direction::Right => lines.top_left,
+                Direction::Left => lines.top_right,
+            },
+            Direction::Down => match curr_dir {
+                Direction::Up => lines.vertical,
+                Direction::Down => lines.vertical,
+                Direction::Right => lines.bottom_left,
+                Direction::Left => lines.bottom_right,
+            },
+            Direction::Left => match curr_dir {
+                Direction::Up => lines.bottom_left,
+                Direction::Down => lines.top_left,
+                Direction::Right => lines.horizontal,
+                Direction::Left => lines.horizontal,
+            },
+            Direction::Right => match curr_dir {
+                Direction::Up => lines.bottom_right,
+                Direction::Down => lines.top_right,
+                Direction::Right => lines.horizontal,
+                Direction::Left => lines.horizontal,
+            },
+        }
+    }
+
+    /// Draws `event`, which left the board with `snake` on it.
+    pub fn draw(&mut self, event: &Event, snake: &Snake) {
+        match *event {
+            // Every segment gets older, and changes shade, on each move.
+            Event::Moved { .. } if self.theme.colors.gradient.is_some() => self.render_snake(snake),
+            Event::Moved {
+                from,
+                to,
+                prev_dir,
+                dir,
+            } => {
+                self.render_body(from, prev_dir, dir, self.theme.colors.body);
+                self.render_head(to, dir);
+            }
+            Event::TailCut { cut, tail, dir } => {
+                self.render_cell(cut, ' ', Color::Reset, false);
+                // Redrawing the new tail also drops any join it had into
+                // the cell just left.
+                let fg = self.theme.colors.body_at(snake.len() - 1, snake.len());
+                self.render_tail(tail, dir, fg);
+            }
+            Event::CandySpawned(cell) => self.render_candy(cell),
+            Event::Ate | Event::Died(_) | Event::BoardFull => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game2::{Difficulty, GlyphSupport, Mode};
+
+    /// An 8 by 6 board in the top left corner; the snake starts on column
+    /// 4, rows 1 to 4, heading up.
+    fn board() -> Board {
+        Board {
+            width: 8,
+            height: 6,
+            cell_width: 2,
+            origin: (0, 0),
+        }
+    }
+
+    fn state() -> GameState {
+        GameState::new((8, 6), Mode::Classic, 7, Difficulty::Normal.speed())
+    }
+
+    fn theme(glyphs: GlyphSupport) -> Theme {
+        Theme::load("mono").unwrap().fit_glyphs(glyphs, 2)
+    }
+
+    fn screen(theme: Theme, state: &GameState) -> Screen<TextGrid> {
+        let mut screen = Screen::with_backend(board(), theme, TextGrid::default());
+        screen.reset((18, 8)).unwrap();
+        screen.render_state(state);
+        screen.flush().unwrap();
+        screen
+    }
+
+    #[test]
+    fn snapshot_shows_board_and_status_line() {
+        let mut state = state();
+        state.candies.insert(Position::new(1, 4));
+        let screen = screen(theme(GlyphSupport::Ascii), &state);
+        assert_eq!(
+            screen.snapshot(&state).unwrap(),
+            "\
++----------------+
+|                |
+|        ^       |
+|        |       |
+|        |       |
+|  *     o       |
+|                |
++----------------+
+score 0  length 4  speed 6.7/s  time 00:00
+"
+        );
+    }
+
+    #[test]
+    fn drawing_a_step_updates_the_board() {
+        let mut state = state();
+        let mut screen = screen(theme(GlyphSupport::Ascii), &state);
+        for event in state.step(Some(Direction::Left)) {
+            screen.draw(&event, &state.snake);
+        }
+        screen.flush().unwrap();
+        assert_eq!(
+            screen.backend.to_string(),
+            "\
++----------------+
+|                |
+|      <=+       |
+|        |       |
+|        o       |
+|                |
+|                |
++----------------+
+"
+        );
+    }
+
+    #[test]
+    fn wide_glyphs_take_two_columns() {
+        let mut state = state();
+        state.candies.insert(Position::new(0, 0));
+        state.candies.insert(Position::new(7, 5));
+        let mut theme = theme(GlyphSupport::Unicode);
+        theme.glyphs.candy = '🍎';
+        let screen = screen(theme, &state);
+        assert_eq!(
+            screen.backend.to_string(),
+            "\
+┌────────────────┐
+│🍎              │
+│        ^       │
+│        │       │
+│        │       │
+│        ╵       │
+│              🍎│
+└────────────────┘
+"
+        );
+    }
+}
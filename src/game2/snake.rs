@@ -1,144 +1,107 @@
-use std::{
-    collections::{BTreeSet, LinkedList},
-    io,
-    ops::Div,
-    sync::{Arc, Mutex},
-    thread, time,
-};
-
-use crossterm::{cursor, style, ExecutableCommand};
-
-use super::commands::{Command, Direction};
+use std::collections::LinkedList;
+
+use super::{board::Position, commands::Direction, mode::Mode, InvalidMoveError};
+
+/// Which way the snake went to get from one of its cells to the next one,
+/// `to`. Cells further apart than one step are neighbours across the edge
+/// of a [`Mode::Wrap`] board.
+pub fn heading(from: Position, to: Position) -> Direction {
+    let (dx, dy) = (to.x as i32 - from.x as i32, to.y as i32 - from.y as i32);
+    let wrapped = dx.abs() > 1 || dy.abs() > 1;
+    match (dx.signum(), dy.signum(), wrapped) {
+        (_, -1, false) | (_, 1, true) => Direction::Up,
+        (_, 1, false) | (_, -1, true) => Direction::Down,
+        (1, _, false) | (-1, _, true) => Direction::Right,
+        _ => Direction::Left,
+    }
+}
 
 pub struct Snake {
-    cells: LinkedList<(u16, u16)>,
-    // term_size: (u16, u16),
-    stdout: Arc<Mutex<io::Stdout>>,
-    dir: Direction
+    cells: LinkedList<Position>,
+    pub dir: Direction,
 }
 
 impl Snake {
-    pub fn new((n_cols, n_rows): (u16, u16), stdout: Arc<Mutex<io::Stdout>>) -> Self {
-        let row_center = n_rows.div(2 as u16);
-        let col_center = n_cols.div(4) * 2;
+    /// Four cells long, in the middle of a `width` by `height` board and
+    /// heading up.
+    pub fn new((width, height): (u16, u16)) -> Self {
+        let x = width / 2;
+        let top = height.saturating_sub(4) / 2;
         Self {
-            cells: (0..4)
-                .into_iter()
-                .map(|row| (col_center, row + row_center))
-                .collect(),
-            // term_size: (n_cols, n_rows),
-            stdout: Arc::clone(&stdout),
-            dir: Direction::Up
+            cells: (top..top + 4).map(|y| Position::new(x, y)).collect(),
+            dir: Direction::Up,
         }
     }
 
-    fn render_snake(prev_dir:Direction, curr_dir:Direction) -> char {
-        match prev_dir {
-            Direction::Up => match curr_dir {
-                Direction::Up => '║',
-                Direction::Down => '║',
-                Direction::Right => '╔',
-                Direction::Left => '╗'
-            },
-            Direction::Down => match curr_dir {
-                Direction::Up => '║',
-                Direction::Down => '║',
-                Direction::Right => '╚',
-                Direction::Left => '╝'
-            }
-            Direction::Left => match curr_dir {
-                Direction::Up => '╚',
-                Direction::Down => '╔',
-                Direction::Right => '═',
-                Direction::Left => '═'
-            },
-            Direction::Right => match curr_dir {
-                Direction::Up => '╝',
-                Direction::Down => '╗',
-                Direction::Right => '═',
-                Direction::Left => '═'
-            }
-        }
+    pub fn head(&self) -> Position {
+        *self.cells.front().unwrap()
     }
 
-    pub fn move_to(
-        &mut self,
-        direction: Direction,
-    ) -> Result<(u16, u16), crossterm::ErrorKind> {
-        let start = self.cells.front().unwrap().clone();
-
-        // 1
-        let (c, r) = start;
-        self.stdout
-            .lock()
-            .unwrap()
-            .execute(cursor::MoveTo(c, r))?
-            .execute(style::Print(Self::render_snake(self.dir, direction)))?;
-
-        // 2
-        let (c, r) = match direction {
-            Direction::Up => (c, r - 1),
-            Direction::Right => (c + 2, r),
-            Direction::Down => (c, r + 1),
-            Direction::Left => {
-                self.stdout
-                    .lock()
-                    .unwrap()
-                    .execute(cursor::MoveTo(c-1, r))?
-                    .execute(style::Print('═'))?;
-                (c - 2, r)
-            }
-        };
-        self.cells.contains(&(c,r)).then(|| panic!());
-
-        self.cells.push_front((c, r));
-        self.stdout
-            .lock()
-            .unwrap()
-            .execute(cursor::MoveTo(c, r))?
-            .execute(style::Print("O"))?;
-        Ok((c,r))
+    pub fn tail(&self) -> Position {
+        *self.cells.back().unwrap()
     }
 
-    pub fn cut_tail_of(&mut self) -> Result<(), crossterm::ErrorKind> {
-        let (c, r) = self.cells.pop_back().unwrap();
-        self.stdout
-            .lock()
-            .unwrap()
-            .execute(cursor::MoveTo(c, r))?
-            .execute(style::Print(" "))?;
-        Ok(())
+    /// Which way the tail goes to follow the rest of the body.
+    pub fn tail_dir(&self) -> Direction {
+        let mut cells = self.cells.iter().rev();
+        match (cells.next(), cells.next()) {
+            (Some(&tail), Some(&next)) => heading(tail, next),
+            _ => self.dir,
+        }
     }
 
-    pub fn run(&mut self, candy_list: Arc<Mutex<BTreeSet<(u16, u16)>>>) {
-
-        let mut render_intvl = time::Duration::from_millis(150);
+    /// Head first.
+    pub fn cells(&self) -> impl Iterator<Item = &Position> {
+        self.cells.iter()
+    }
 
-        loop {
-            let new_dir = match Command::new() {
-                Some(Command::Move(new_dir)) => {
-                    if self.dir.is_opposite(&new_dir) {
-                        self.dir
-                    } else {
-                        new_dir
-                    }
-                }
-                Some(Command::Quit) => break,
-                _ => self.dir,
-            };
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
 
-            let cell = self.move_to(new_dir).unwrap();
-            self.dir = new_dir;
+    /// Whether moving the head onto `cell` runs into the body. The tail only
+    /// counts when the snake `grows` this tick, otherwise it moves out of the
+    /// way first.
+    pub fn bites(&self, cell: &Position, grows: bool) -> bool {
+        if !grows && self.cells.back() == Some(cell) {
+            return false;
+        }
+        self.cells.contains(cell)
+    }
 
-            // does cell contain candy
-            if candy_list.lock().unwrap().contains(&cell) {
-                candy_list.lock().unwrap().remove(&cell);
-                render_intvl -= time::Duration::from_millis(1);
-            } else {
-                self.cut_tail_of().unwrap();
+    /// The cell one step in `direction` on a `width` by `height` board.
+    /// Stepping off the board is an error in [`Mode::Classic`] and comes out
+    /// on the opposite side in [`Mode::Wrap`].
+    pub fn next_cell(
+        &self,
+        direction: Direction,
+        (width, height): (u16, u16),
+        mode: Mode,
+    ) -> Result<Position, InvalidMoveError> {
+        let Position { x, y } = self.head();
+        let (x, y) = match direction {
+            Direction::Up => (x as i32, y as i32 - 1),
+            Direction::Right => (x as i32 + 1, y as i32),
+            Direction::Down => (x as i32, y as i32 + 1),
+            Direction::Left => (x as i32 - 1, y as i32),
+        };
+        let (width, height) = (width as i32, height as i32);
+        let (x, y) = match mode {
+            Mode::Classic if x < 0 || x >= width || y < 0 || y >= height => {
+                return Err(InvalidMoveError::HitWall)
             }
+            Mode::Classic => (x, y),
+            Mode::Wrap => (x.rem_euclid(width), y.rem_euclid(height)),
+        };
+        Ok(Position::new(x as u16, y as u16))
+    }
 
-            thread::sleep(render_intvl);
-        }
+    pub fn push_head(&mut self, cell: Position, direction: Direction) {
+        self.cells.push_front(cell);
+        self.dir = direction;
+    }
+
+    pub fn cut_tail_of(&mut self) -> Position {
+        self.cells.pop_back().unwrap()
     }
 }
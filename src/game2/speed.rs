@@ -0,0 +1,250 @@
+use std::{convert::TryFrom, time::Duration};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SpeedError {
+    #[error("ticks have to last at least 1ms")]
+    ZeroTick,
+    #[error("floor_ms ({floor_ms}) is longer than initial_ms ({initial_ms})")]
+    FloorAboveInitial { initial_ms: u64, floor_ms: u64 },
+    #[error("factor ({0}) has to be above 0 and at most 1")]
+    Factor(f64),
+}
+
+/// How the tick shortens as the snake eats. In the config file, the
+/// `[speed.curve]` table names one by `kind`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SpeedCurve {
+    Constant,
+    /// Takes `step_ms` off the tick for every candy.
+    Linear {
+        step_ms: u64,
+    },
+    /// Multiplies the tick by `factor` for every candy.
+    Exponential {
+        factor: f64,
+    },
+    /// Takes `step_ms` off the tick once every `per_level` candies.
+    Stepped {
+        per_level: u32,
+        step_ms: u64,
+    },
+}
+
+/// The `[speed]` table of the config file.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "SpeedTable")]
+pub struct Speed {
+    pub initial_ms: u64,
+    /// The tick never gets shorter than this.
+    pub floor_ms: u64,
+    pub curve: SpeedCurve,
+}
+
+/// [`Speed`] as written, before it is checked.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpeedTable {
+    initial_ms: u64,
+    floor_ms: u64,
+    curve: SpeedCurve,
+}
+
+impl TryFrom<SpeedTable> for Speed {
+    type Error = SpeedError;
+
+    fn try_from(table: SpeedTable) -> Result<Self, Self::Error> {
+        Speed::new(table.initial_ms, table.floor_ms, table.curve)
+    }
+}
+
+impl Speed {
+    /// Starts at a tick of `initial_ms` that `curve` shortens down to
+    /// `floor_ms`. Neither can be zero, the floor cannot be above the start,
+    /// and the curve cannot make ticks longer.
+    pub fn new(initial_ms: u64, floor_ms: u64, curve: SpeedCurve) -> Result<Self, SpeedError> {
+        if initial_ms == 0 || floor_ms == 0 {
+            return Err(SpeedError::ZeroTick);
+        }
+        if floor_ms > initial_ms {
+            return Err(SpeedError::FloorAboveInitial {
+                initial_ms,
+                floor_ms,
+            });
+        }
+        if let SpeedCurve::Exponential { factor } = curve {
+            if !(factor > 0.0 && factor <= 1.0) {
+                return Err(SpeedError::Factor(factor));
+            }
+        }
+        Ok(Self {
+            initial_ms,
+            floor_ms,
+            curve,
+        })
+    }
+
+    /// Time between two moves once `candies` have been eaten.
+    pub fn tick(&self, candies: u32) -> Duration {
+        let ms = match self.curve {
+            SpeedCurve::Constant => self.initial_ms,
+            SpeedCurve::Linear { step_ms } => self
+                .initial_ms
+                .saturating_sub(step_ms.saturating_mul(candies as u64)),
+            SpeedCurve::Exponential { factor } => {
+                (self.initial_ms as f64 * factor.powi(candies as i32)) as u64
+            }
+            SpeedCurve::Stepped { per_level, step_ms } => {
+                let level = (candies / per_level.max(1)) as u64;
+                self.initial_ms
+                    .saturating_sub(step_ms.saturating_mul(level))
+            }
+        };
+        Duration::from_millis(ms.max(self.floor_ms))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub fn speed(self) -> Speed {
+        match self {
+            Difficulty::Easy => Speed {
+                initial_ms: 200,
+                floor_ms: 100,
+                curve: SpeedCurve::Linear { step_ms: 1 },
+            },
+            Difficulty::Normal => Speed {
+                initial_ms: 150,
+                floor_ms: 60,
+                curve: SpeedCurve::Linear { step_ms: 1 },
+            },
+            Difficulty::Hard => Speed {
+                initial_ms: 120,
+                floor_ms: 40,
+                curve: SpeedCurve::Exponential { factor: 0.97 },
+            },
+            Difficulty::Insane => Speed {
+                initial_ms: 80,
+                floor_ms: 30,
+                curve: SpeedCurve::Stepped {
+                    per_level: 5,
+                    step_ms: 10,
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(curve: SpeedCurve, candies: &[u32]) -> Vec<u64> {
+        let speed = Speed::new(100, 40, curve).unwrap();
+        candies
+            .iter()
+            .map(|&candies| speed.tick(candies).as_millis() as u64)
+            .collect()
+    }
+
+    #[test]
+    fn curves_shorten_the_tick_down_to_the_floor() {
+        assert_eq!(ticks(SpeedCurve::Constant, &[0, 50]), [100, 100]);
+        assert_eq!(
+            ticks(SpeedCurve::Linear { step_ms: 10 }, &[0, 1, 5, 100]),
+            [100, 90, 50, 40]
+        );
+        assert_eq!(
+            ticks(SpeedCurve::Exponential { factor: 0.5 }, &[0, 1, 2]),
+            [100, 50, 40]
+        );
+        assert_eq!(
+            ticks(
+                SpeedCurve::Stepped {
+                    per_level: 3,
+                    step_ms: 20
+                },
+                &[0, 2, 3, 6, 9]
+            ),
+            [100, 100, 80, 60, 40]
+        );
+    }
+
+    fn parse(toml: &str) -> Result<Speed, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    #[test]
+    fn reads_a_speed_table() {
+        let speed =
+            parse("initial_ms = 150\nfloor_ms = 60\n[curve]\nkind = \"linear\"\nstep_ms = 2")
+                .unwrap();
+        assert_eq!((speed.initial_ms, speed.floor_ms), (150, 60));
+        assert!(matches!(speed.curve, SpeedCurve::Linear { step_ms: 2 }));
+    }
+
+    #[test]
+    fn rejects_bad_speed_tables() {
+        let table = |limits: &str, curve: &str| format!("{}\n[curve]\n{}", limits, curve);
+        let constant = "kind = \"constant\"";
+        for (table, message) in [
+            (
+                table("initial_ms = 0\nfloor_ms = 0", constant),
+                "at least 1ms",
+            ),
+            (
+                table("initial_ms = 50\nfloor_ms = 80", constant),
+                "longer than",
+            ),
+            (
+                table(
+                    "initial_ms = 50\nfloor_ms = 20",
+                    "kind = \"exponential\"\nfactor = 2.0",
+                ),
+                "at most 1",
+            ),
+            (
+                table(
+                    "initial_ms = 50\nfloor_ms = 20",
+                    "kind = \"exponential\"\nfactor = -0.5",
+                ),
+                "above 0",
+            ),
+            (
+                table("initial_ms = 50\nfloor_ms = 20\nstep = 1", constant),
+                "unknown field `step`",
+            ),
+            (
+                table(
+                    "initial_ms = 50\nfloor_ms = 20",
+                    "kind = \"linear\"\nstepms = 1",
+                ),
+                "unknown field `stepms`",
+            ),
+        ] {
+            let err = parse(&table).unwrap_err().to_string();
+            assert!(err.contains(message), "{:?} gave {:?}", table, err);
+        }
+    }
+
+    #[test]
+    fn rejects_factors_that_are_not_a_number() {
+        let curve = SpeedCurve::Exponential { factor: f64::NAN };
+        assert!(matches!(
+            Speed::new(100, 40, curve),
+            Err(SpeedError::Factor(_))
+        ));
+    }
+}
@@ -0,0 +1,287 @@
+use std::{collections::BTreeSet, time};
+
+use super::{
+    board::Position,
+    candy_spawner::{CandySpawner, SpawnTimer},
+    commands::Direction,
+    mode::Mode,
+    score::Score,
+    snake::Snake,
+    speed::Speed,
+    InvalidMoveError,
+};
+
+/// Something that happened on the board during a call to [`GameState::step`]
+/// or [`GameState::spawn_candy`]. Renderers only ever look at these.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Moved {
+        from: Position,
+        to: Position,
+        prev_dir: Direction,
+        dir: Direction,
+    },
+    /// The tail left `cut`; the snake now ends at `tail`, which goes `dir`
+    /// next.
+    TailCut {
+        cut: Position,
+        tail: Position,
+        dir: Direction,
+    },
+    Ate,
+    CandySpawned(Position),
+    Died(InvalidMoveError),
+    BoardFull,
+}
+
+/// The rules of the game, without any terminal attached.
+pub struct GameState {
+    /// Cells across and down.
+    pub board: (u16, u16),
+    pub mode: Mode,
+    pub snake: Snake,
+    pub candies: BTreeSet<Position>,
+    pub tick: time::Duration,
+    speed: Speed,
+    pub alive: bool,
+    pub score: Score,
+    spawner: CandySpawner,
+    spawn_timer: SpawnTimer,
+}
+
+impl GameState {
+    pub fn new(board: (u16, u16), mode: Mode, seed: u64, speed: Speed) -> Self {
+        Self {
+            board,
+            mode,
+            snake: Snake::new(board),
+            candies: BTreeSet::new(),
+            tick: speed.tick(0),
+            speed,
+            alive: true,
+            score: Score::default(),
+            spawner: CandySpawner::seeded(board, seed),
+            spawn_timer: SpawnTimer::new(time::Duration::from_secs(4)),
+        }
+    }
+
+    fn die(&mut self, reason: InvalidMoveError) -> Vec<Event> {
+        self.alive = false;
+        vec![Event::Died(reason)]
+    }
+
+    /// Runs one tick: turns if `turn` is a legal new direction, advances the
+    /// snake by one cell, lets it eat, then drops candy if one is due.
+    /// Running into the body, or into the frame in [`Mode::Classic`], ends
+    /// the round with a [`Event::Died`]; filling the whole board wins it
+    /// with a [`Event::BoardFull`].
+    pub fn step(&mut self, turn: Option<Direction>) -> Vec<Event> {
+        if !self.alive {
+            return vec![];
+        }
+
+        let prev_dir = self.snake.dir;
+        let dir = match turn {
+            Some(new_dir) if !prev_dir.is_opposite(&new_dir) => new_dir,
+            _ => prev_dir,
+        };
+
+        let from = self.snake.head();
+        let to = match self.snake.next_cell(dir, self.board, self.mode) {
+            Ok(cell) => cell,
+            Err(reason) => return self.die(reason),
+        };
+        let grows = self.candies.contains(&to);
+        if self.snake.bites(&to, grows) {
+            return self.die(InvalidMoveError::HitSelf);
+        }
+
+        // The tail goes first so that chasing it into its own cell works.
+        let mut events = vec![];
+        if !grows {
+            let cut = self.snake.cut_tail_of();
+            events.push(Event::TailCut {
+                cut,
+                tail: self.snake.tail(),
+                dir: self.snake.tail_dir(),
+            });
+        }
+        self.snake.push_head(to, dir);
+        events.push(Event::Moved {
+            from,
+            to,
+            prev_dir,
+            dir,
+        });
+        if grows {
+            self.candies.remove(&to);
+            self.score.eat();
+            self.tick = self.speed.tick(self.score.candies);
+            events.push(Event::Ate);
+        }
+        self.score.tick(self.tick);
+        let (width, height) = self.board;
+        if self.snake.len() == width as usize * height as usize {
+            self.alive = false;
+            events.push(Event::BoardFull);
+            return events;
+        }
+        if self.spawn_timer.advance(self.tick) {
+            events.extend(self.spawn_candy());
+        }
+        events
+    }
+
+    /// Drops a new candy on a free cell, if there is one left.
+    pub fn spawn_candy(&mut self) -> Option<Event> {
+        let candy = self.spawner.get_new_candy(&self.snake, &self.candies)?;
+        self.candies.insert(candy);
+        Some(Event::CandySpawned(candy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game2::Difficulty;
+
+    /// A 10 by 10 board; the snake starts on column 5, rows 3 to 6, heading
+    /// up.
+    fn state(mode: Mode) -> GameState {
+        GameState::new((10, 10), mode, 1, Difficulty::Normal.speed())
+    }
+
+    fn cells(state: &GameState) -> Vec<Position> {
+        state.snake.cells().copied().collect()
+    }
+
+    fn died(events: &[Event]) -> Option<InvalidMoveError> {
+        events.iter().find_map(|event| match event {
+            Event::Died(reason) => Some(*reason),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn moves_one_cell() {
+        let mut state = state(Mode::Classic);
+        let events = state.step(None);
+        assert_eq!(
+            cells(&state),
+            [(5, 2), (5, 3), (5, 4), (5, 5)].map(|(x, y)| Position::new(x, y))
+        );
+        assert!(matches!(
+            events[..],
+            [
+                Event::TailCut { cut, .. },
+                Event::Moved { from, to, .. },
+            ] if cut == Position::new(5, 6) && from == Position::new(5, 3) && to == Position::new(5, 2)
+        ));
+    }
+
+    #[test]
+    fn eating_grows_the_snake() {
+        let mut state = state(Mode::Classic);
+        state.candies.insert(Position::new(5, 2));
+        let events = state.step(None);
+        assert_eq!(state.snake.len(), 5);
+        assert_eq!(state.snake.tail(), Position::new(5, 6));
+        assert!(state.candies.is_empty());
+        assert_eq!(state.score.candies, 1);
+        assert!(events.iter().any(|event| matches!(event, Event::Ate)));
+    }
+
+    #[test]
+    fn candy_never_lands_on_the_snake() {
+        let mut state = state(Mode::Classic);
+        while let Some(event) = state.spawn_candy() {
+            let Event::CandySpawned(candy) = event else {
+                panic!("unexpected {:?}", event)
+            };
+            assert!(!state.snake.cells().any(|&cell| cell == candy));
+        }
+        assert_eq!(state.candies.len(), 10 * 10 - 4);
+    }
+
+    #[test]
+    fn a_board_full_of_candy_is_not_a_win() {
+        let mut state = state(Mode::Wrap);
+        while state.spawn_candy().is_some() {}
+        assert!(state.alive);
+        let events = state.step(None);
+        assert!(state.alive);
+        assert!(!events.iter().any(|event| matches!(event, Event::BoardFull)));
+    }
+
+    #[test]
+    fn filling_the_board_wins_on_the_tick_it_happens() {
+        // One column of five cells: the snake takes four, heading up.
+        let mut state = GameState::new((1, 5), Mode::Wrap, 1, Difficulty::Normal.speed());
+        state.candies.insert(Position::new(0, 4));
+        let events = state.step(None);
+        assert_eq!(state.snake.len(), 5);
+        assert!(!state.alive);
+        assert!(matches!(events[..], [.., Event::Ate, Event::BoardFull]));
+    }
+
+    #[test]
+    fn classic_walls_kill() {
+        let mut state = state(Mode::Classic);
+        for _ in 0..3 {
+            state.step(None);
+        }
+        assert_eq!(state.snake.head(), Position::new(5, 0));
+        assert_eq!(died(&state.step(None)), Some(InvalidMoveError::HitWall));
+        assert!(!state.alive);
+        assert!(state.step(None).is_empty());
+    }
+
+    #[test]
+    fn wrap_comes_out_on_the_other_side() {
+        let mut state = state(Mode::Wrap);
+        for _ in 0..4 {
+            state.step(None);
+        }
+        assert!(state.alive);
+        assert_eq!(state.snake.head(), Position::new(5, 9));
+        state.step(Some(Direction::Left));
+        for _ in 0..4 {
+            state.step(None);
+        }
+        assert_eq!(state.snake.head(), Position::new(0, 9));
+        state.step(None);
+        assert_eq!(state.snake.head(), Position::new(9, 9));
+    }
+
+    #[test]
+    fn biting_itself_kills() {
+        let mut state = state(Mode::Classic);
+        state.candies.insert(Position::new(4, 3));
+        state.step(Some(Direction::Left));
+        state.step(Some(Direction::Down));
+        // Grown to five cells, the tail is in (5, 5) and (5, 4) is still
+        // part of the body.
+        let events = state.step(Some(Direction::Right));
+        assert_eq!(died(&events), Some(InvalidMoveError::HitSelf));
+    }
+
+    #[test]
+    fn head_can_follow_the_tail_into_its_cell() {
+        let mut state = state(Mode::Classic);
+        state.step(Some(Direction::Left));
+        state.step(Some(Direction::Down));
+        assert_eq!(state.snake.tail(), Position::new(5, 4));
+        let events = state.step(Some(Direction::Right));
+        assert_eq!(died(&events), None);
+        assert_eq!(state.snake.head(), Position::new(5, 4));
+        assert_eq!(state.snake.len(), 4);
+    }
+
+    #[test]
+    fn turning_back_is_ignored() {
+        let mut state = state(Mode::Classic);
+        state.step(Some(Direction::Down));
+        assert_eq!(state.snake.head(), Position::new(5, 2));
+        assert_eq!(state.snake.dir, Direction::Up);
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    convert::TryFrom,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crossterm::style::Color;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use super::{
+    colors::{self, ColorSupport, GRADIENT_STEPS},
+    commands::Direction,
+    frame,
+};
+
+/// Themes that ship with the game, by name.
+const BUILTIN: &[(&str, &str)] = &[
+    ("classic", include_str!("themes/classic.toml")),
+    ("neon", include_str!("themes/neon.toml")),
+    ("mono", include_str!("themes/mono.toml")),
+];
+
+pub const DEFAULT_THEME: &str = "classic";
+
+#[derive(Error, Debug)]
+pub enum ThemeError {
+    #[error("unknown theme {0:?}")]
+    Unknown(String),
+    #[error("could not read {0}: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("theme {0}: {1}")]
+    Parse(String, toml::de::Error),
+}
+
+/// Colors and glyphs to draw the game with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub colors: Palette,
+    pub glyphs: Glyphs,
+}
+
+/// Colors are names like `dark_green`, `#rrggbb`, a 256 color index, or
+/// `reset` for the terminal's own.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Palette {
+    #[serde(deserialize_with = "color")]
+    pub frame: Color,
+    /// Behind the cells inside the frame.
+    #[serde(deserialize_with = "color")]
+    pub background: Color,
+    #[serde(deserialize_with = "color")]
+    pub head: Color,
+    #[serde(deserialize_with = "color")]
+    pub body: Color,
+    /// If set, the body fades from `body` behind the head to this color at
+    /// the tail.
+    #[serde(default, deserialize_with = "optional_color")]
+    pub tail: Option<Color>,
+    /// `body` fading into `tail`, worked out by [`Theme::fit_colors`].
+    #[serde(skip)]
+    pub gradient: Option<[Color; GRADIENT_STEPS]>,
+    #[serde(deserialize_with = "color")]
+    pub candy: Color,
+    /// The status line and messages.
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Glyphs {
+    /// Pointing the way the snake goes.
+    pub head: Arrows,
+    /// The last segment, pointing the way it goes next.
+    pub tail: Arrows,
+    pub candy: char,
+    pub border: Lines,
+    /// Straight pieces and bends the body is made of.
+    pub body: Lines,
+}
+
+/// The pieces of a line that turns corners, like the frame around the board.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lines {
+    pub horizontal: char,
+    pub vertical: char,
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+}
+
+/// A glyph for each way something can point.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Arrows {
+    pub up: char,
+    pub down: char,
+    pub left: char,
+    pub right: char,
+}
+
+/// How much of Unicode the terminal's font can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSupport {
+    Ascii,
+    /// Box drawing and other common symbols.
+    Unicode,
+    /// Unicode plus the icons in the private use area that Nerd Fonts add.
+    Nerd,
+}
+
+impl FromStr for GlyphSupport {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ascii" => Ok(GlyphSupport::Ascii),
+            "unicode" => Ok(GlyphSupport::Unicode),
+            "nerd" => Ok(GlyphSupport::Nerd),
+            _ => Err(()),
+        }
+    }
+}
+
+impl GlyphSupport {
+    fn shows(self, symbol: char) -> bool {
+        match self {
+            GlyphSupport::Ascii => symbol.is_ascii(),
+            GlyphSupport::Unicode => !frame::is_private_use(symbol),
+            GlyphSupport::Nerd => true,
+        }
+    }
+}
+
+impl Glyphs {
+    /// Shows on any terminal.
+    const ASCII: Glyphs = Glyphs {
+        head: Arrows {
+            up: '^',
+            down: 'v',
+            left: '<',
+            right: '>',
+        },
+        tail: Arrows {
+            up: 'o',
+            down: 'o',
+            left: 'o',
+            right: 'o',
+        },
+        candy: '*',
+        border: Lines {
+            horizontal: '-',
+            vertical: '|',
+            top_left: '+',
+            top_right: '+',
+            bottom_left: '+',
+            bottom_right: '+',
+        },
+        body: Lines {
+            horizontal: '=',
+            vertical: '|',
+            top_left: '+',
+            top_right: '+',
+            bottom_left: '+',
+            bottom_right: '+',
+        },
+    };
+}
+
+impl Arrows {
+    pub fn get(self, dir: Direction) -> char {
+        match dir {
+            Direction::Up => self.up,
+            Direction::Down => self.down,
+            Direction::Left => self.left,
+            Direction::Right => self.right,
+        }
+    }
+
+    fn fit(self, support: GlyphSupport, ascii: Arrows, max_width: usize) -> Self {
+        let fit = |glyph, fallback| fit(glyph, fallback, support, max_width);
+        Self {
+            up: fit(self.up, ascii.up),
+            down: fit(self.down, ascii.down),
+            left: fit(self.left, ascii.left),
+            right: fit(self.right, ascii.right),
+        }
+    }
+}
+
+impl Lines {
+    fn fit(self, support: GlyphSupport, ascii: Lines) -> Self {
+        let fit = |glyph, fallback| fit(glyph, fallback, support, 1);
+        Self {
+            horizontal: fit(self.horizontal, ascii.horizontal),
+            vertical: fit(self.vertical, ascii.vertical),
+            top_left: fit(self.top_left, ascii.top_left),
+            top_right: fit(self.top_right, ascii.top_right),
+            bottom_left: fit(self.bottom_left, ascii.bottom_left),
+            bottom_right: fit(self.bottom_right, ascii.bottom_right),
+        }
+    }
+}
+
+/// `glyph` if the terminal shows it at `support` in at most `max_width`
+/// columns, `fallback` otherwise.
+fn fit(glyph: char, fallback: char, support: GlyphSupport, max_width: usize) -> char {
+    if support.shows(glyph) && (1..=max_width).contains(&frame::width(glyph)) {
+        glyph
+    } else {
+        fallback
+    }
+}
+
+fn parse_color(name: &str) -> Option<Color> {
+    if name == "reset" {
+        return Some(Color::Reset);
+    }
+    if let Some(hex) = name.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb {
+            r: (rgb >> 16) as u8,
+            g: (rgb >> 8) as u8,
+            b: rgb as u8,
+        });
+    }
+    if let Ok(index) = name.parse() {
+        return Some(Color::AnsiValue(index));
+    }
+    Color::try_from(name).ok()
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    parse_color(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown color {:?}", name)))
+}
+
+fn optional_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    color(deserializer).map(Some)
+}
+
+impl Palette {
+    /// The color of the body segment `age` cells behind the head, on a snake
+    /// `len` cells long.
+    pub fn body_at(&self, age: usize, len: usize) -> Color {
+        match self.gradient {
+            Some(shades) => shades[age * (GRADIENT_STEPS - 1) / len.saturating_sub(1).max(1)],
+            None => self.body,
+        }
+    }
+}
+
+impl Theme {
+    fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake-cli").join("themes"))
+    }
+
+    /// Finds a theme by `name`: a path to a theme file, a file `name.toml`
+    /// in the user's themes directory, or one of the built in themes.
+    pub fn load(name: &str) -> Result<Self, ThemeError> {
+        let path = Path::new(name);
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return Self::load_from(path.to_path_buf());
+        }
+        match Self::dir().map(|dir| dir.join(name).with_extension("toml")) {
+            Some(path) if path.exists() => Self::load_from(path),
+            _ => {
+                let (_, text) = BUILTIN
+                    .iter()
+                    .find(|(builtin, _)| *builtin == name)
+                    .ok_or_else(|| ThemeError::Unknown(name.to_string()))?;
+                toml::from_str(text).map_err(|err| ThemeError::Parse(name.to_string(), err))
+            }
+        }
+    }
+
+    fn load_from(path: PathBuf) -> Result<Self, ThemeError> {
+        let text = fs::read_to_string(&path).map_err(|err| ThemeError::Read(path.clone(), err))?;
+        toml::from_str(&text).map_err(|err| ThemeError::Parse(path.display().to_string(), err))
+    }
+
+    /// Swaps every glyph the terminal cannot show at `support` for its ASCII
+    /// counterpart. So are head, tail and candy glyphs wider than a cell of
+    /// `cell_width` columns, and lines wider than one column.
+    pub fn fit_glyphs(self, support: GlyphSupport, cell_width: u16) -> Self {
+        let (glyphs, ascii) = (self.glyphs, Glyphs::ASCII);
+        let cell_width = cell_width as usize;
+        Self {
+            glyphs: Glyphs {
+                head: glyphs.head.fit(support, ascii.head, cell_width),
+                tail: glyphs.tail.fit(support, ascii.tail, cell_width),
+                candy: fit(glyphs.candy, ascii.candy, support, cell_width),
+                border: glyphs.border.fit(support, ascii.border),
+                body: glyphs.body.fit(support, ascii.body),
+            },
+            ..self
+        }
+    }
+
+    /// The same theme, with every color swapped for the closest one the
+    /// terminal can show. The body gradient is worked out before that, so
+    /// its shades come out as close as they can.
+    pub fn fit_colors(self, support: ColorSupport) -> Self {
+        let fit = |color| support.downsample(color);
+        let colors = self.colors;
+        Self {
+            colors: Palette {
+                frame: fit(colors.frame),
+                background: fit(colors.background),
+                head: fit(colors.head),
+                body: fit(colors.body),
+                tail: colors.tail.map(fit),
+                gradient: colors
+                    .tail
+                    .and_then(|tail| colors::gradient(colors.body, tail))
+                    .map(|shades| shades.map(fit)),
+                candy: fit(colors.candy),
+                text: fit(colors.text),
+            },
+            ..self
+        }
+    }
+}
@@ -1,26 +1,84 @@
-
-#[allow(unused)]
+#[allow(unused, clippy::all)]
 mod game1;
 
+mod cli;
 mod game2;
 
-use crossterm::style;
-use game2::Game;
+use std::{env, error::Error, process};
+
+use cli::{Args, SpeedArg, USAGE};
+use game2::{ColorSupport, Config, Game, HighScores, KeyMap, Settings, Speed, Theme, DEFAULT_THEME};
 
 mod terminal;
 // use game1::{Game, Settings, terminal::Terminal};
-use terminal::Terminal;
-
-
-
-
+use terminal::{detect_colors, detect_glyphs, Terminal};
 
 fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2)
+    });
+    if args.help {
+        print!("{}", USAGE);
+        return;
+    }
+    if args.scores {
+        print!("{}", HighScores::load());
+        return;
+    }
+
+    let config = match args.config {
+        Some(path) => Config::load_from(path),
+        None => Config::load(),
+    }
+    .unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1)
+    });
+    let keys = KeyMap::new(&config.keys).unwrap_or_else(|err| {
+        eprintln!("invalid key bindings: {}", err);
+        process::exit(1)
+    });
 
-    let term = Terminal::new(0.8, 0.8, style::Color::Grey);
+    let speed = match args.speed {
+        Some(SpeedArg::Preset(difficulty)) => difficulty.speed(),
+        Some(SpeedArg::InitialMs(ms)) => {
+            let speed = config.speed();
+            Speed::new(ms, speed.floor_ms.min(ms), speed.curve).unwrap_or_else(|err| {
+                eprintln!("invalid value \"{}\" for --speed: {}", ms, err);
+                process::exit(2)
+            })
+        }
+        None => config.speed(),
+    };
+    let theme_name = args.theme.or(config.theme);
+    let theme = Theme::load(theme_name.as_deref().unwrap_or(DEFAULT_THEME)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1)
+    });
+    let colors = if args.no_color { ColorSupport::None } else { detect_colors() };
+    let theme = theme.fit_colors(colors);
 
-    let mut game = Game::new(term);
-    
-    game.run();
+    let term = Terminal::new(args.width, args.height).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1)
+    });
+    let glyphs = args.glyphs.or(config.glyphs).unwrap_or_else(detect_glyphs);
+    let theme = theme.fit_glyphs(glyphs, term.board.cell_width);
 
+    let settings = Settings {
+        seed: args.seed,
+        mode: args.mode,
+        keys,
+        speed,
+        theme,
+        snapshot: args.snapshot,
+    };
+    let mut game = Game::new(term, settings);
+    game.run().unwrap_or_else(|err| {
+        // crossterm only says that an IO error happened, not which one.
+        let cause = err.source().map(|source| format!(": {}", source));
+        eprintln!("{}{}", err, cause.unwrap_or_default());
+        process::exit(1)
+    });
 }
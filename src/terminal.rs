@@ -1,52 +1,132 @@
+use std::env;
 use std::io;
-use std::sync::{Arc, Mutex};
+use std::panic;
+use std::sync::Once;
 use crossterm::{cursor, ExecutableCommand};
-use crossterm::terminal::{self, ClearType};
-use crossterm::style::{self, Color};
+use crossterm::terminal;
+use crossterm::style;
+use thiserror::Error;
 
+use crate::game2::{Board, BoardError, ColorSupport, Dimension, GlyphSupport};
+
+/// Terminal columns per board cell, so cells come out roughly square.
+const CELL_WIDTH: u16 = 2;
+
+#[derive(Error, Debug)]
+pub enum TerminalError {
+    #[error("could not read the terminal size: {0}")]
+    Size(crossterm::ErrorKind),
+    #[error(transparent)]
+    Board(#[from] BoardError),
+}
 
 pub struct Terminal {
-    pub size: (u16, u16),
-    pub color: Color,
-    pub stdout: Arc<Mutex<io::Stdout>>
+    /// The board size asked for, to lay out again on a new terminal size.
+    width: Dimension,
+    height: Dimension,
+    pub board: Board,
 }
 
-impl Terminal {
-    pub fn n_cols(&self) -> u16 {
-        self.size.0
+/// Guesses what the terminal can show from `TERM` and the locale. Nerd Font
+/// icons are never assumed, there is no telling whether the font has them.
+pub fn detect_glyphs() -> GlyphSupport {
+    let term = env::var("TERM").unwrap_or_default();
+    if matches!(term.as_str(), "dumb" | "linux" | "vt100" | "vt220") {
+        return GlyphSupport::Ascii;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default()
+        .to_lowercase();
+    if locale.contains("utf-8") || locale.contains("utf8") {
+        GlyphSupport::Unicode
+    } else {
+        GlyphSupport::Ascii
     }
+}
 
-    pub fn n_rows(&self) -> u16 {
-        self.size.1
+/// Guesses how many colors the terminal has from `NO_COLOR`, `COLORTERM`
+/// and `TERM`, the way most terminal programs do.
+pub fn detect_colors() -> ColorSupport {
+    if env::var("NO_COLOR").is_ok_and(|value| !value.is_empty()) {
+        return ColorSupport::None;
+    }
+    let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorSupport::TrueColor;
+    }
+    let term = env::var("TERM").unwrap_or_default();
+    if term == "dumb" {
+        ColorSupport::None
+    } else if term.contains("256color") {
+        ColorSupport::Ansi256
+    } else {
+        ColorSupport::Ansi16
     }
+}
+
+/// Keeps the terminal in game mode for as long as it lives. Dropping it, on
+/// any path out of the game including a panic, gives the user their shell
+/// back.
+#[must_use]
+pub struct TerminalGuard;
 
-    pub fn new(width:f32, height:f32, color: Color) -> Self {
-        let size = {
-            let size = terminal::size().unwrap(); 
-            let n_rows = (  width * size.1 as f32) as u16;
-            let n_cols = ( height * size.0 as f32) as u16;
-            (n_cols, n_rows)
-        };
-        Self { 
-            size,
-            color,
-            stdout: Arc::new(Mutex::new(io::stdout()))
-        }
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
     }
+}
+
+/// Undoes everything `Terminal::initialize` did. Goes through its own stdout
+/// handle so it still works in the middle of a panic.
+fn restore() {
+    let mut stdout = io::stdout();
+    stdout.execute(style::ResetColor).ok();
+    stdout.execute(cursor::Show).ok();
+    stdout.execute(terminal::LeaveAlternateScreen).ok();
+    terminal::disable_raw_mode().ok();
+}
+
+/// The default hook prints the panic message before the guard is dropped,
+/// which would land on the alternate screen and vanish with it.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            default_hook(info);
+        }));
+    });
+}
 
-    pub fn initialize(&mut self) {
-        terminal::enable_raw_mode().unwrap();
-        self.stdout.lock().unwrap().execute(terminal::SetSize(self.n_rows() + 3, self.n_cols() + 3)).unwrap()
-            .execute(terminal::Clear(terminal::ClearType::All)).unwrap()
-            .execute(crossterm::cursor::Hide).unwrap();
+impl Terminal {
+    /// Lays the board out centered on the current terminal.
+    pub fn new(width: Dimension, height: Dimension) -> Result<Self, TerminalError> {
+        let size = terminal::size().map_err(TerminalError::Size)?;
+        Ok(Self {
+            width,
+            height,
+            board: Board::fit(width, height, CELL_WIDTH, size)?,
+        })
     }
 
-    pub fn reset(&mut self) {
-        self.stdout.lock().unwrap().execute(terminal::Clear(ClearType::All)).unwrap()
-            .execute(cursor::Show).unwrap()
-            .execute(style::ResetColor).unwrap();
-        terminal::disable_raw_mode().unwrap();
+    /// Lays a board of the size asked for out on a terminal of `size`.
+    pub fn layout(&self, size: (u16, u16)) -> Result<Board, BoardError> {
+        Board::fit(self.width, self.height, CELL_WIDTH, size)
     }
-}
 
+    /// Switches to game mode. Whatever got switched before an error is
+    /// undone when the guard is dropped on the way out.
+    pub fn initialize(&mut self) -> Result<TerminalGuard, crossterm::ErrorKind> {
+        install_panic_hook();
+        let guard = TerminalGuard;
+        terminal::enable_raw_mode()?;
+        io::stdout().execute(terminal::EnterAlternateScreen)?
+            .execute(terminal::Clear(terminal::ClearType::All))?
+            .execute(crossterm::cursor::Hide)?;
+        Ok(guard)
+    }
+}
 